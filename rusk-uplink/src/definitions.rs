@@ -307,6 +307,7 @@ impl<'a> RawTransaction<'a> {
 pub struct RawEvent {
     data: Vec<u8>,
     name: String,
+    topics: Vec<[u8; 32]>,
 }
 
 impl RawEvent {
@@ -320,6 +321,7 @@ impl RawEvent {
         RawEvent {
             data: ser.spill_bytes(|bytes| Vec::from(bytes)),
             name: name.into(),
+            topics: Vec::new(),
         }
     }
 
@@ -331,9 +333,16 @@ impl RawEvent {
         Self {
             data: data.into(),
             name: name.into(),
+            topics: Vec::new(),
         }
     }
 
+    /// Index the event by the given topics.
+    pub fn with_topics<T: Into<Vec<[u8; 32]>>>(mut self, topics: T) -> Self {
+        self.topics = topics.into();
+        self
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -341,6 +350,10 @@ impl RawEvent {
     pub fn data(&self) -> &[u8] {
         &self.data[..]
     }
+
+    pub fn topics(&self) -> &[[u8; 32]] {
+        &self.topics[..]
+    }
 }
 
 // todo! find better way
@@ -6,9 +6,10 @@
 
 extern crate alloc;
 
-use core::cell::UnsafeCell;
+use core::cell::{Cell, UnsafeCell};
 
 use alloc::boxed::Box;
+use alloc::rc::Rc;
 use alloc::vec::Vec;
 
 use microkelvin::{OffsetLen, Store, Token, TokenBuffer};
@@ -58,6 +59,7 @@ struct AbiStoreInner {
 
 pub struct AbiStore {
     inner: UnsafeCell<AbiStoreInner>,
+    overflowed: Rc<Cell<bool>>,
 }
 
 impl Fallible for AbiStore {
@@ -141,8 +143,15 @@ impl AbiStore {
     pub fn new(buf: &mut [u8]) -> Self {
         AbiStore {
             inner: UnsafeCell::new(AbiStoreInner::new(buf)),
+            overflowed: Rc::new(Cell::new(false)),
         }
     }
+
+    /// Returns a flag set once a write to the store no longer fit the buffer
+    /// it was created with, and spilled over to memory of its own.
+    pub fn overflowed(&self) -> Rc<Cell<bool>> {
+        self.overflowed.clone()
+    }
 }
 
 impl Store for AbiStore {
@@ -170,6 +179,7 @@ impl Store for AbiStore {
     fn extend(&self, buffer: &mut TokenBuffer) -> Result<(), ()> {
         let inner = unsafe { &mut *self.inner.get() };
         inner.extend();
+        self.overflowed.set(true);
         let slice = unsafe { &mut *inner.data };
         buffer.remap(slice);
         Ok(())
@@ -5,9 +5,14 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use crate::StoreContext;
+use rkyv::{Archive, Deserialize};
+
+#[cfg(not(feature = "host"))]
+use crate::{AbiStore, RETURN_BUFFER_FLAG};
+#[cfg(not(feature = "host"))]
 use microkelvin::{OffsetLen, StoreSerializer};
-use rkyv::ser::Serializer;
-use rkyv::{Archive, Deserialize, Serialize};
+#[cfg(not(feature = "host"))]
+use rkyv::{ser::Serializer, Serialize};
 
 pub fn get_state_arg<S, P>(
     written_state: u32,
@@ -37,27 +42,58 @@ where
     (state, arg)
 }
 
-pub fn q_return<R>(ret: &R, store: StoreContext) -> u32
+/// Returns the length of the buffer to run an entry point again with, when
+/// its result did not fit one of `buffer_len` bytes.
+#[cfg(not(feature = "host"))]
+fn retry_len(buffer_len: usize) -> u32 {
+    let len = buffer_len.saturating_mul(2);
+    RETURN_BUFFER_FLAG
+        | core::cmp::min(len, !RETURN_BUFFER_FLAG as usize) as u32
+}
+
+/// Writes the result of a query to `buffer`, returning its length - or the
+/// [`RETURN_BUFFER_FLAG`] if it does not fit.
+#[cfg(not(feature = "host"))]
+pub fn q_return<R>(ret: &R, buffer: &mut [u8]) -> u32
 where
     R: Archive + Serialize<StoreSerializer<OffsetLen>>,
 {
-    let mut ser = store.serializer();
-    let buffer_len = ser.serialize_value(ret).unwrap()
+    let buffer_len = buffer.len();
+    let store = AbiStore::new(buffer);
+    let overflowed = store.overflowed();
+
+    let mut ser = StoreContext::new(store).serializer();
+    let result_len = ser.serialize_value(ret).unwrap()
         + core::mem::size_of::<<R as Archive>::Archived>();
-    buffer_len as u32
+
+    if overflowed.get() {
+        return retry_len(buffer_len);
+    }
+    result_len as u32
 }
 
-pub fn t_return<S, R>(state: &S, ret: &R, store: StoreContext) -> [u32; 2]
+/// Writes the new state and the result of a transaction to `buffer`,
+/// returning the length of the state and where the result ends - or the
+/// [`RETURN_BUFFER_FLAG`] in place of the former if they do not fit.
+#[cfg(not(feature = "host"))]
+pub fn t_return<S, R>(state: &S, ret: &R, buffer: &mut [u8]) -> [u32; 2]
 where
     S: Serialize<StoreSerializer<OffsetLen>>,
     R: Archive + Serialize<StoreSerializer<OffsetLen>>,
 {
-    let mut ser = store.serializer();
+    let buffer_len = buffer.len();
+    let store = AbiStore::new(buffer);
+    let overflowed = store.overflowed();
+
+    let mut ser = StoreContext::new(store).serializer();
     let state_len = ser.serialize_value(state).unwrap()
         + core::mem::size_of::<<S as Archive>::Archived>();
 
     let return_len = ser.serialize_value(ret).unwrap()
         + core::mem::size_of::<<R as Archive>::Archived>();
 
+    if overflowed.get() {
+        return [retry_len(buffer_len), 0];
+    }
     [state_len as u32, return_len as u32]
 }
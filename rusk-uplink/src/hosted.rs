@@ -12,13 +12,125 @@ pub use crate::{
 };
 
 use alloc::string::String;
+use alloc::vec::Vec;
 
 use bytecheck::CheckBytes;
 use microkelvin::{OffsetLen, StoreRef, StoreSerializer};
+use rkyv::ser::Serializer;
 use rkyv::validation::validators::DefaultValidator;
 use rkyv::{Archive, Deserialize, Serialize};
 
-const BUFFER_SIZE_LIMIT: usize = 1024 * 16;
+/// Capacity of the buffer the results of calls to other contracts are
+/// written to, unless the arguments of the call are larger.
+const BUFFER_SIZE: usize = 1024 * 64;
+
+/// Set in the return of the `query` host call when the call failed. The
+/// remaining bits then hold the length of the encoded [`CallError`] written
+/// to the buffer.
+pub const QUERY_ERROR_FLAG: u32 = 1 << 31;
+
+/// Set in the return of the `transact` host call when the call failed. The
+/// remaining bits then hold the length of the encoded [`CallError`] written
+/// to the buffer.
+pub const TRANSACT_ERROR_FLAG: u64 = 1 << 63;
+
+/// Set in the return of the `query` host call when the result of the call
+/// does not fit the buffer. The remaining bits then hold the length of the
+/// result, and the call is to be made again with a buffer at least as large.
+pub const QUERY_BUFFER_FLAG: u32 = 1 << 30;
+
+/// Set in the return of the `transact` host call when the result of the call
+/// does not fit the buffer. The remaining bits then hold the length of the
+/// state and result, and the call - which was undone - is to be made again
+/// with a buffer at least as large.
+pub const TRANSACT_BUFFER_FLAG: u64 = 1 << 62;
+
+/// Set in the return of a query or transaction entry point when its result
+/// does not fit the buffer it was given. The remaining bits then hold the
+/// length of the buffer the call is to be run again with. Transactions return
+/// it in place of the length of their state.
+pub const RETURN_BUFFER_FLAG: u32 = 1 << 31;
+
+/// Returned by the `storage_get` host call when there is no value stored
+/// under the key.
+pub const STORAGE_ABSENT: u32 = u32::MAX;
+
+/// Label the transcripts of the proofs checked with [`verify_proof`] are
+/// initialized with. Provers must use the same label.
+pub const PROOF_TRANSCRIPT_LABEL: &[u8] = b"dusk-network";
+
+/// Error of a call to another contract, as seen by the calling contract.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub enum CallError {
+    /// The callee panicked with the given message.
+    Panic(String),
+    /// The callee ran out of gas.
+    OutOfGas,
+    /// There is no contract at the called address.
+    UnknownContract,
+    /// The data passed to or returned by the callee is invalid.
+    InvalidData,
+    /// The callee has no query or transaction with the given name.
+    UnknownMethod,
+    /// A transaction was called as a query, or a query as a transaction.
+    MethodKindMismatch,
+}
+
+impl CallError {
+    const PANIC: u8 = 1;
+    const OUT_OF_GAS: u8 = 2;
+    const UNKNOWN_CONTRACT: u8 = 3;
+    const INVALID_DATA: u8 = 4;
+    const UNKNOWN_METHOD: u8 = 5;
+    const METHOD_KIND_MISMATCH: u8 = 6;
+
+    /// Returns the code identifying the kind of error.
+    pub fn code(&self) -> u8 {
+        match self {
+            CallError::Panic(_) => Self::PANIC,
+            CallError::OutOfGas => Self::OUT_OF_GAS,
+            CallError::UnknownContract => Self::UNKNOWN_CONTRACT,
+            CallError::InvalidData => Self::INVALID_DATA,
+            CallError::UnknownMethod => Self::UNKNOWN_METHOD,
+            CallError::MethodKindMismatch => Self::METHOD_KIND_MISMATCH,
+        }
+    }
+
+    /// Encodes the error as its code followed by its message, if any.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.push(self.code());
+        if let CallError::Panic(msg) = self {
+            bytes.extend_from_slice(msg.as_bytes());
+        }
+        bytes
+    }
+
+    /// Decodes an error encoded with [`to_bytes`].
+    ///
+    /// [`to_bytes`]: CallError::to_bytes
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        match bytes.split_first() {
+            Some((&Self::PANIC, msg)) => {
+                CallError::Panic(String::from_utf8_lossy(msg).into_owned())
+            }
+            Some((&Self::OUT_OF_GAS, _)) => CallError::OutOfGas,
+            Some((&Self::UNKNOWN_CONTRACT, _)) => CallError::UnknownContract,
+            Some((&Self::UNKNOWN_METHOD, _)) => CallError::UnknownMethod,
+            Some((&Self::METHOD_KIND_MISMATCH, _)) => {
+                CallError::MethodKindMismatch
+            }
+            _ => CallError::InvalidData,
+        }
+    }
+}
+
+impl From<ArchiveError> for CallError {
+    fn from(_: ArchiveError) -> Self {
+        CallError::InvalidData
+    }
+}
 
 // declare available host-calls
 pub mod external {
@@ -29,6 +141,7 @@ pub mod external {
             target: &u8,
             buf: &u8,
             buf_len: u32,
+            buf_cap: u32,
             name: &u8,
             name_len: u32,
             gas_limit: u64,
@@ -38,12 +151,64 @@ pub mod external {
             target: &u8,
             buf: &u8,
             buf_len: u32,
+            buf_cap: u32,
             name: &u8,
             name_len: u32,
             gas_limit: u64,
         ) -> u64;
 
-        pub fn emit(buf: &u8, buf_len: u32, name: &u8, name_len: u32);
+        pub fn emit(
+            buf: &u8,
+            buf_len: u32,
+            name: &u8,
+            name_len: u32,
+            topics: &u8,
+            topics_len: u32,
+        );
+
+        pub fn deploy(
+            code: &u8,
+            code_len: u32,
+            state: &u8,
+            state_len: u32,
+            id: &mut u8,
+        );
+
+        pub fn self_destruct();
+
+        pub fn upgrade(code: &u8, code_len: u32);
+
+        pub fn hash(buf: &u8, len: u32, ret: &mut u8);
+
+        pub fn sha256(buf: &u8, len: u32, ret: &mut u8);
+
+        pub fn keccak256(buf: &u8, len: u32, ret: &mut u8);
+
+        pub fn poseidon_hash(scalars: &u8, len: u32, ret: &mut u8) -> u32;
+
+        pub fn schnorr_verify(pk: &u8, msg: &u8, sig: &u8) -> u32;
+
+        pub fn bls_verify(pk: &u8, sig: &u8, msg: &u8, msg_len: u32) -> u32;
+
+        pub fn verify_proof(
+            verifier_data: &u8,
+            verifier_data_len: u32,
+            proof: &u8,
+            proof_len: u32,
+            public_inputs: &u8,
+            public_inputs_len: u32,
+        ) -> u32;
+
+        pub fn storage_get(
+            key: &u8,
+            key_len: u32,
+            buf: &mut u8,
+            buf_len: u32,
+        ) -> u32;
+
+        pub fn storage_set(key: &u8, key_len: u32, value: &u8, value_len: u32);
+
+        pub fn storage_remove(key: &u8, key_len: u32) -> u32;
 
         pub fn callee(buffer: &mut u8);
 
@@ -65,26 +230,45 @@ pub fn debug_raw(debug_string: impl AsRef<str>) {
     unsafe { external::debug(&buffer[0], string.len() as i32) }
 }
 
+/// Returns a buffer holding `data`, large enough for the result of a call.
+fn call_buffer(data: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::from(data);
+    buf.resize(core::cmp::max(data.len(), BUFFER_SIZE), 0);
+    buf
+}
+
 /// Call another contract at address `target`
 pub fn query_raw(
     target: &ContractId,
     raw_query: &RawQuery,
     gas_limit: u64,
-) -> Result<ReturnValue, ArchiveError> {
-    let mut buf = [0u8; BUFFER_SIZE_LIMIT];
+) -> Result<ReturnValue, CallError> {
+    let mut buf = call_buffer(raw_query.data());
     let data_len = raw_query.data().len();
-    buf[..data_len].copy_from_slice(raw_query.data());
     let name = raw_query.name();
-    let result_offset = unsafe {
-        external::query(
-            &target.as_bytes()[0],
-            &buf[0],
-            data_len as u32,
-            &name.as_bytes()[0],
-            name.len() as u32,
-            gas_limit,
-        )
+    let result_offset = loop {
+        let result_offset = unsafe {
+            external::query(
+                &target.as_bytes()[0],
+                &buf[0],
+                data_len as u32,
+                buf.len() as u32,
+                &name.as_bytes()[0],
+                name.len() as u32,
+                gas_limit,
+            )
+        };
+        if result_offset & QUERY_BUFFER_FLAG == 0 {
+            break result_offset;
+        }
+        // The result didn't fit, so ask again with a large enough buffer.
+        // The arguments were left untouched at the start of the buffer.
+        buf.resize((result_offset & !QUERY_BUFFER_FLAG) as usize, 0);
     };
+    if result_offset & QUERY_ERROR_FLAG != 0 {
+        let error_len = (result_offset & !QUERY_ERROR_FLAG) as usize;
+        return Err(CallError::from_bytes(&buf[..error_len]));
+    }
     let result = ReturnValue::new(&buf[..result_offset as usize]);
     Ok(result)
 }
@@ -98,7 +282,7 @@ pub fn query<Q>(
     q: Q,
     gas_limit: u64,
     mut store: StoreRef<OffsetLen>,
-) -> Result<Q::Return, ArchiveError>
+) -> Result<Q::Return, CallError>
 where
     Q: Query + Serialize<StoreSerializer<OffsetLen>>,
     Q::Return: Archive,
@@ -111,7 +295,7 @@ where
 
     let cast = result
         .cast::<Q::Return>()
-        .map_err(|_| ArchiveError::ArchiveValidationError)?;
+        .map_err(|_| CallError::InvalidData)?;
 
     let deserialized: Q::Return =
         cast.deserialize(&mut store).expect("Infallible");
@@ -126,36 +310,51 @@ pub fn transact_raw<Slf>(
     raw_transaction: &RawTransaction,
     gas_limit: u64,
     mut store: StoreRef<OffsetLen>,
-) -> Result<ReturnValue, ArchiveError>
+) -> Result<ReturnValue, CallError>
 where
     Slf: Archive,
     <Slf as Archive>::Archived: Deserialize<Slf, StoreRef<OffsetLen>>,
 {
-    let mut buf = [0u8; BUFFER_SIZE_LIMIT];
+    let mut buf = call_buffer(raw_transaction.data());
     let data_len = raw_transaction.data().len();
-    buf[..data_len].copy_from_slice(raw_transaction.data());
     let name = raw_transaction.name();
 
-    let offsets = unsafe {
-        external::transact(
-            &target.as_bytes()[0],
-            &buf[0],
-            data_len as u32,
-            &name.as_bytes()[0],
-            name.len() as u32,
-            gas_limit,
-        )
+    let offsets = loop {
+        let offsets = unsafe {
+            external::transact(
+                &target.as_bytes()[0],
+                &buf[0],
+                data_len as u32,
+                buf.len() as u32,
+                &name.as_bytes()[0],
+                name.len() as u32,
+                gas_limit,
+            )
+        };
+        if offsets & TRANSACT_BUFFER_FLAG == 0 {
+            break offsets;
+        }
+        // The result didn't fit, so ask again with a large enough buffer.
+        // The arguments were left untouched at the start of the buffer.
+        buf.resize((offsets & !TRANSACT_BUFFER_FLAG) as usize, 0);
     };
+    if offsets & TRANSACT_ERROR_FLAG != 0 {
+        let error_len = (offsets & !TRANSACT_ERROR_FLAG) as usize;
+        return Err(CallError::from_bytes(&buf[..error_len]));
+    }
     let result_offset = (offsets & 0xffffffff00000000) >> 32;
     let state_offset = offsets & 0xffffffff;
     let result = ReturnValue::with_state(
         &buf[state_offset as usize..result_offset as usize],
         &buf[..state_offset as usize],
     );
-    let cast_state = result.cast_state::<Slf>();
-    let deserialized_state: Slf =
-        cast_state.deserialize(&mut store).expect("Infallible");
-    *slf = deserialized_state;
+    // Host modules have no state to return, leaving the caller's untouched
+    if result.state_len() != 0 {
+        let cast_state = result.cast_state::<Slf>();
+        let deserialized_state: Slf =
+            cast_state.deserialize(&mut store).expect("Infallible");
+        *slf = deserialized_state;
+    }
 
     Ok(result)
 }
@@ -170,7 +369,7 @@ pub fn transact<T, Slf>(
     transaction: T,
     gas_limit: u64,
     mut store: StoreRef<OffsetLen>,
-) -> Result<T::Return, ArchiveError>
+) -> Result<T::Return, CallError>
 where
     T: Transaction + Serialize<StoreSerializer<OffsetLen>>,
     T::Return: Archive,
@@ -186,7 +385,7 @@ where
 
     let cast = result
         .cast::<T::Return>()
-        .map_err(|_| ArchiveError::ArchiveValidationError)?;
+        .map_err(|_| CallError::InvalidData)?;
 
     let deserialized_result: T::Return =
         cast.deserialize(&mut store).expect("Infallible");
@@ -195,17 +394,18 @@ where
 }
 
 pub fn emit_raw(raw_event: &RawEvent) {
-    let mut buf = [0u8; BUFFER_SIZE_LIMIT];
-    let data_len = raw_event.data().len();
-    buf[..data_len].copy_from_slice(raw_event.data());
+    let data = raw_event.data();
     let name = raw_event.name();
+    let topics = raw_event.topics();
 
     unsafe {
         external::emit(
-            &buf[0],
-            data_len as u32,
+            data.first().unwrap_or(&0),
+            data.len() as u32,
             &name.as_bytes()[0],
             name.len() as u32,
+            topics.first().map(|topic| &topic[0]).unwrap_or(&0),
+            (topics.len() * 32) as u32,
         )
     }
 }
@@ -219,6 +419,220 @@ where
     emit_raw(&raw_event);
 }
 
+/// Emit an event indexed by the given topics, which can later be used to
+/// look it up in the receipts of the calls.
+pub fn emit_indexed<S, E>(
+    name: S,
+    topics: &[[u8; 32]],
+    event: E,
+    store: StoreRef<OffsetLen>,
+) where
+    S: Into<String>,
+    E: Archive + Serialize<StoreSerializer<OffsetLen>>,
+{
+    let raw_event = RawEvent::new(name, event, &store).with_topics(topics);
+    emit_raw(&raw_event);
+}
+
+/// Deploy a contract with the given bytecode and serialized initial state,
+/// returning its id.
+///
+/// The id is derived from the id of the deploying contract and the number of
+/// contracts it deployed before.
+pub fn deploy_raw(code: &[u8], init_state: &[u8]) -> ContractId {
+    let mut result = ContractId::default();
+    unsafe {
+        external::deploy(
+            code.first().unwrap_or(&0),
+            code.len() as u32,
+            init_state.first().unwrap_or(&0),
+            init_state.len() as u32,
+            &mut result.as_bytes_mut()[0],
+        )
+    };
+    result
+}
+
+/// Deploy a contract with the given bytecode and initial state, returning its
+/// id.
+pub fn deploy<S>(
+    code: &[u8],
+    init_state: &S,
+    store: StoreRef<OffsetLen>,
+) -> ContractId
+where
+    S: Archive + Serialize<StoreSerializer<OffsetLen>>,
+{
+    let mut ser = store.serializer();
+    ser.serialize_value(init_state).unwrap();
+    let state = ser.spill_bytes(|bytes| Vec::from(bytes));
+
+    deploy_raw(code, &state)
+}
+
+/// Replace the bytecode of the calling contract, keeping its id, state,
+/// storage and nonce.
+///
+/// The current call carries on with the previous bytecode, and the following
+/// calls use the new one, which must be able to read the contract's state.
+pub fn upgrade(code: &[u8]) {
+    unsafe { external::upgrade(code.first().unwrap_or(&0), code.len() as u32) }
+}
+
+/// Remove the calling contract from the state.
+///
+/// The contract is removed right away, and is only restored if the current
+/// call or one of its callers fails. Further calls to the contract fail with
+/// an unknown contract error.
+pub fn self_destruct() {
+    unsafe { external::self_destruct() }
+}
+
+/// Returns the value stored under `key` in the calling contract's storage.
+pub fn storage_get(key: &[u8]) -> Option<Vec<u8>> {
+    let mut buf = alloc::vec![0u8; 256];
+    loop {
+        let len = unsafe {
+            external::storage_get(
+                key.first().unwrap_or(&0),
+                key.len() as u32,
+                &mut buf[0],
+                buf.len() as u32,
+            )
+        };
+        if len == STORAGE_ABSENT {
+            return None;
+        }
+
+        let len = len as usize;
+        if len <= buf.len() {
+            buf.truncate(len);
+            return Some(buf);
+        }
+        // The value didn't fit, so ask again with a large enough buffer
+        buf.resize(len, 0);
+    }
+}
+
+/// Stores `value` under `key` in the calling contract's storage.
+///
+/// Unlike the contract's state, the storage is not passed to the contract on
+/// every call, making it suitable for large collections.
+pub fn storage_set(key: &[u8], value: &[u8]) {
+    unsafe {
+        external::storage_set(
+            key.first().unwrap_or(&0),
+            key.len() as u32,
+            value.first().unwrap_or(&0),
+            value.len() as u32,
+        )
+    }
+}
+
+/// Removes the value stored under `key` in the calling contract's storage,
+/// returning whether there was one.
+pub fn storage_remove(key: &[u8]) -> bool {
+    unsafe {
+        external::storage_remove(key.first().unwrap_or(&0), key.len() as u32)
+            != 0
+    }
+}
+
+/// Returns the blake2b-256 hash of `bytes`, computed by the host.
+pub fn hash(bytes: &[u8]) -> [u8; 32] {
+    let mut result = [0u8; 32];
+    unsafe {
+        external::hash(
+            bytes.first().unwrap_or(&0),
+            bytes.len() as u32,
+            &mut result[0],
+        )
+    };
+    result
+}
+
+/// Returns the sha256 hash of `bytes`, computed by the host.
+pub fn sha256(bytes: &[u8]) -> [u8; 32] {
+    let mut result = [0u8; 32];
+    unsafe {
+        external::sha256(
+            bytes.first().unwrap_or(&0),
+            bytes.len() as u32,
+            &mut result[0],
+        )
+    };
+    result
+}
+
+/// Returns the keccak256 hash of `bytes`, computed by the host.
+pub fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    let mut result = [0u8; 32];
+    unsafe {
+        external::keccak256(
+            bytes.first().unwrap_or(&0),
+            bytes.len() as u32,
+            &mut result[0],
+        )
+    };
+    result
+}
+
+/// Returns the poseidon hash of the given BLS12-381 scalars, computed by the
+/// host.
+///
+/// Returns `None` if any of the scalars is not canonically encoded.
+pub fn poseidon_hash(scalars: &[[u8; 32]]) -> Option<[u8; 32]> {
+    let mut result = [0u8; 32];
+    let hashed = unsafe {
+        external::poseidon_hash(
+            scalars.first().map(|s| &s[0]).unwrap_or(&0),
+            (scalars.len() * 32) as u32,
+            &mut result[0],
+        )
+    };
+    (hashed != 0).then(|| result)
+}
+
+/// Verifies a Schnorr signature of the message scalar `msg` against the
+/// public key `pk`.
+pub fn schnorr_verify(pk: &[u8; 32], msg: &[u8; 32], sig: &[u8; 64]) -> bool {
+    unsafe { external::schnorr_verify(&pk[0], &msg[0], &sig[0]) != 0 }
+}
+
+/// Verifies a BLS signature of `msg` against the public key `pk`.
+pub fn bls_verify(pk: &[u8; 96], sig: &[u8; 48], msg: &[u8]) -> bool {
+    unsafe {
+        external::bls_verify(
+            &pk[0],
+            &sig[0],
+            msg.first().unwrap_or(&0),
+            msg.len() as u32,
+        ) != 0
+    }
+}
+
+/// Verifies a PLONK proof against the given verifier data and public
+/// inputs, encoded as BLS12-381 scalars.
+///
+/// Returns `false` if the proof is invalid or any of the inputs is
+/// malformed.
+pub fn verify_proof(
+    verifier_data: &[u8],
+    proof: &[u8],
+    public_inputs: &[[u8; 32]],
+) -> bool {
+    unsafe {
+        external::verify_proof(
+            verifier_data.first().unwrap_or(&0),
+            verifier_data.len() as u32,
+            proof.first().unwrap_or(&0),
+            proof.len() as u32,
+            public_inputs.first().map(|s| &s[0]).unwrap_or(&0),
+            (public_inputs.len() * 32) as u32,
+        ) != 0
+    }
+}
+
 ///Returns the hash of the currently executing contract
 pub fn callee() -> ContractId {
     let mut result = ContractId::default();
@@ -30,5 +30,8 @@ pub use debug::*;
 pub mod framing;
 pub use framing::*;
 
+pub mod proof;
+pub use proof::*;
+
 #[cfg(not(feature = "host"))]
 mod items;
@@ -0,0 +1,180 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use blake2b_simd::Params;
+use bytecheck::CheckBytes;
+use rkyv::{Archive, Deserialize, Serialize};
+
+use crate::ContractId;
+
+/// Domain tag prepended to the preimage of the hash of a leaf of the state
+/// tree.
+pub const LEAF_TAG: u8 = 0;
+
+/// Domain tag prepended to the preimage of the hash of a node of the state
+/// tree, so that it can never be taken for the hash of a leaf.
+pub const NODE_TAG: u8 = 1;
+
+/// Number of children of a node of the state tree.
+pub const TREE_ARITY: usize = 4;
+
+/// Maximum depth of the state tree.
+pub const TREE_DEPTH: usize = 32;
+
+/// Hash of a contract's bytecode.
+pub fn code_hash(code: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(Params::new().hash_length(32).hash(code).as_bytes());
+    hash
+}
+
+/// The slots taken by the contract with the given id in the nodes along its
+/// path in the state tree, from the root down.
+///
+/// The slot at depth `d` is taken from the `d`-th byte of the hash of the id,
+/// mirroring how contracts are placed in the tree.
+pub fn tree_slots(id: &ContractId) -> [usize; TREE_DEPTH] {
+    let hash = Params::new().hash_length(32).hash(id.as_bytes());
+
+    let mut slots = [0; TREE_DEPTH];
+    for (slot, byte) in slots.iter_mut().zip(hash.as_bytes()) {
+        *slot = *byte as usize % TREE_ARITY;
+    }
+    slots
+}
+
+/// Hash of a contract in the state tree, committing to its id, the hash of
+/// its bytecode, the root of its storage, its nonce and its state.
+pub fn leaf_hash(
+    id: &ContractId,
+    code_hash: &[u8; 32],
+    storage_root: &[u8; 32],
+    nonce: u64,
+    state: &[u8],
+) -> [u8; 32] {
+    let mut state_hasher = Params::new().hash_length(32).to_state();
+    state_hasher
+        .update(&[LEAF_TAG])
+        .update(id.as_bytes())
+        .update(code_hash)
+        .update(storage_root)
+        .update(&nonce.to_le_bytes())
+        .update(state);
+
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(state_hasher.finalize().as_bytes());
+    hash
+}
+
+/// Combines the hash of a node in the state tree with the hash of one of its
+/// children.
+pub fn combine_hash(node: &[u8; 32], child: &[u8; 32]) -> [u8; 32] {
+    let mut state_hasher = Params::new().hash_length(32).to_state();
+    state_hasher.update(&[NODE_TAG]).update(node).update(child);
+
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(state_hasher.finalize().as_bytes());
+    hash
+}
+
+/// The children of a node on the path to a contract in the state tree.
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct ProofLevel {
+    /// Hashes of the children of the node, by slot. The slot on the path is
+    /// left empty, as are the slots holding no child.
+    pub siblings: [Option<[u8; 32]>; TREE_ARITY],
+}
+
+impl ProofLevel {
+    /// Hash of the node, given the hash of its child on the path at `slot`.
+    ///
+    /// Returns `None` if the slot is taken by a sibling.
+    fn node_hash(&self, slot: usize, child: &[u8; 32]) -> Option<[u8; 32]> {
+        if self.siblings[slot].is_some() {
+            return None;
+        }
+
+        let hash = self.siblings.iter().enumerate().fold(
+            [0u8; 32],
+            |node, (i, sibling)| match (i == slot, sibling) {
+                (true, _) => combine_hash(&node, child),
+                (false, Some(sibling)) => combine_hash(&node, sibling),
+                (false, None) => node,
+            },
+        );
+
+        Some(hash)
+    }
+}
+
+/// Proof of the inclusion of a contract's state in the root of a network
+/// state.
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct StateProof {
+    /// The hash of the contract's bytecode
+    pub code_hash: [u8; 32],
+    /// The root of the contract's storage
+    pub storage_root: [u8; 32],
+    /// The number of contracts deployed by the contract
+    pub nonce: u64,
+    /// The levels of the path to the contract, from its leaf up to the root
+    pub levels: Vec<ProofLevel>,
+}
+
+impl StateProof {
+    /// Verifies that the contract with the given `id` has the given `state`
+    /// in the state with the given `root`.
+    ///
+    /// The bytecode, storage and nonce of the contract are committed to by
+    /// the proof's [`code_hash`](StateProof::code_hash),
+    /// [`storage_root`](StateProof::storage_root) and
+    /// [`nonce`](StateProof::nonce).
+    ///
+    /// The slot of the contract at each level is derived from its id, and
+    /// proofs with a sibling in that slot or with more levels than the tree
+    /// can hold are rejected.
+    pub fn verify(
+        &self,
+        root: &[u8; 32],
+        id: &ContractId,
+        state: &[u8],
+    ) -> bool {
+        let depth = self.levels.len();
+        if depth == 0 || depth > TREE_DEPTH {
+            return false;
+        }
+
+        let slots = tree_slots(id);
+        let leaf = leaf_hash(
+            id,
+            &self.code_hash,
+            &self.storage_root,
+            self.nonce,
+            state,
+        );
+
+        // The levels go from the leaf up, so the first is the deepest
+        let hash = self
+            .levels
+            .iter()
+            .zip(slots[..depth].iter().rev())
+            .try_fold(leaf, |hash, (level, slot)| {
+                level.node_hash(*slot, &hash)
+            });
+
+        hash.as_ref() == Some(root)
+    }
+}
@@ -19,9 +19,69 @@ use args::*;
 mod derive_args;
 use derive_args::*;
 
-const SCRATCH_NAME: &str = "scratch";
+/// Size of the scratch buffer every contract starts with. It is grown on
+/// demand by the host through the exported `scratch_grow` function.
 const SCRATCH_SIZE: usize = 65536;
 
+/// Name of the custom section describing the entry points of a contract,
+/// matching `rusk_vm::ABI_SECTION_NAME`.
+const ABI_SECTION_NAME: &str = "rusk_abi";
+
+/// Generates a static that places a record describing an entry point in the
+/// [`ABI_SECTION_NAME`] section of the module, which the linker concatenates
+/// with the records of the other entry points.
+fn abi_record(
+    kind: &str,
+    name: &str,
+    arg_t: &syn::Type,
+    ret_t: &syn::Type,
+) -> proc_macro2::TokenStream {
+    let record = format!(
+        "{}\t{}\t{}\t{}\n",
+        kind,
+        name,
+        type_name(arg_t),
+        type_name(ret_t)
+    );
+    let len = record.len();
+    let bytes = proc_macro2::Literal::byte_string(record.as_bytes());
+
+    quote! {
+        #[used]
+        #[link_section = #ABI_SECTION_NAME]
+        static ABI_RECORD: [u8; #len] = *#bytes;
+    }
+}
+
+/// Renders a type as it would be written, without the spaces `quote` puts
+/// between all of its tokens - `Option < u64 >` becomes `Option<u64>`. Spaces
+/// are only kept between words, as in `&mut T`, and after separators, as in
+/// `[u8; 32]`.
+fn type_name(ty: &syn::Type) -> String {
+    let is_word = |c: Option<char>| {
+        c.map_or(false, |c| c.is_alphanumeric() || c == '_' || c == '\'')
+    };
+
+    let tokens = quote!(#ty).to_string();
+    let mut name = String::with_capacity(tokens.len());
+
+    let mut chars = tokens.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != ' ' {
+            name.push(c);
+            continue;
+        }
+
+        let prev = name.chars().last();
+        let next = chars.peek().copied();
+        if matches!(prev, Some(',' | ';')) || (is_word(prev) && is_word(next)) {
+            name.push(' ');
+        }
+    }
+
+    name
+}
+
 #[proc_macro_attribute]
 pub fn execute(attrs: TokenStream, input: TokenStream) -> TokenStream {
     let q_impl = parse_macro_input!(input as syn::ItemImpl);
@@ -32,11 +92,11 @@ pub fn execute(attrs: TokenStream, input: TokenStream) -> TokenStream {
     let arg_types = non_self_argument_types(&q_impl_method.sig);
 
     let arg_t = arg_types.get(0).unwrap();
-    let _ret_t = return_type_of_sig(&q_impl_method.sig);
+    let ret_t = return_type_of_sig(&q_impl_method.sig);
     let state_t = q_impl.self_ty.as_ref();
+    let record = abi_record("query", &q_fn_name, arg_t, &ret_t);
 
     let wrapper_fun_name = format_ident!("{}", q_fn_name);
-    let scratch_name = format_ident!("{}", SCRATCH_NAME);
     let gen = quote! {
 
         #q_impl
@@ -46,11 +106,13 @@ pub fn execute(attrs: TokenStream, input: TokenStream) -> TokenStream {
             use rusk_uplink::{
                 get_state_arg, q_return, AbiStore, StoreContext
             };
-            use crate::scratch_mod::scratch;
+            use crate::scratch_mod::buffer;
+
+            #record
 
             #[no_mangle]
             fn #wrapper_fun_name(written_state: u32, written_data: u32) -> u32 {
-                let (state_arg, mut rest) = unsafe { #scratch_name.split_at_mut(written_data as usize) };
+                let (state_arg, mut rest) = unsafe { buffer().split_at_mut(written_data as usize) };
                 let store =
                     StoreContext::new(AbiStore::new(unsafe { &mut rest }));
                 let (state, arg): (#state_t, #arg_t) = unsafe {
@@ -65,9 +127,7 @@ pub fn execute(attrs: TokenStream, input: TokenStream) -> TokenStream {
                 let res: <#arg_t as Query>::Return =
                     state.execute(arg, store.clone());
 
-                let scratch_mem = unsafe { &mut #scratch_name[..] };
-                let store = StoreContext::new(AbiStore::new(scratch_mem));
-                unsafe { q_return(&res, store) }
+                unsafe { q_return(&res, buffer()) }
             }
         };
     };
@@ -84,11 +144,11 @@ pub fn apply(attrs: TokenStream, input: TokenStream) -> TokenStream {
     let arg_types = non_self_argument_types(&t_impl_method.sig);
 
     let arg_t = arg_types.get(0).unwrap();
-    let _ret_t = return_type_of_sig(&t_impl_method.sig);
+    let ret_t = return_type_of_sig(&t_impl_method.sig);
     let state_t = t_impl.self_ty.as_ref();
+    let record = abi_record("transaction", &t_fn_name, arg_t, &ret_t);
 
     let wrapper_fun_name = format_ident!("{}", t_fn_name);
-    let scratch_name = format_ident!("{}", SCRATCH_NAME);
     let gen = quote! {
 
         #t_impl
@@ -98,11 +158,13 @@ pub fn apply(attrs: TokenStream, input: TokenStream) -> TokenStream {
             use rusk_uplink::{
                 get_state_arg, t_return, AbiStore, StoreContext
             };
-            use crate::scratch_mod::scratch;
+            use crate::scratch_mod::buffer;
+
+            #record
 
             #[no_mangle]
             fn #wrapper_fun_name(written_state: u32, written_data: u32) -> [u32; 2] {
-                let (state_arg, mut rest) = unsafe { #scratch_name.split_at_mut(written_data as usize) };
+                let (state_arg, mut rest) = unsafe { buffer().split_at_mut(written_data as usize) };
                 let store =
                     StoreContext::new(AbiStore::new(unsafe { &mut rest }));
                 let (mut state, arg): (#state_t, #arg_t) = unsafe {
@@ -117,9 +179,7 @@ pub fn apply(attrs: TokenStream, input: TokenStream) -> TokenStream {
                 let res: <#arg_t as Transaction>::Return =
                     state.apply(arg, store.clone());
 
-                let scratch_mem = unsafe { &mut #scratch_name[..] };
-                let store = StoreContext::new(AbiStore::new(scratch_mem));
-                unsafe { t_return(&state, &res, store) }
+                unsafe { t_return(&state, &res, buffer()) }
             }
         };
     };
@@ -170,8 +230,39 @@ pub fn init(_attrs: TokenStream, input: TokenStream) -> TokenStream {
     let gen = quote! {
         #[cfg(target_family = "wasm")]
         mod scratch_mod {
+            extern crate alloc;
+            use alloc::vec::Vec;
+
             #[no_mangle]
             pub static mut scratch: [u8; #SCRATCH_SIZE] = [0u8; #SCRATCH_SIZE];
+
+            static mut GROWN: Vec<u8> = Vec::new();
+
+            /// Returns the buffer used to exchange data with the host
+            pub unsafe fn buffer() -> &'static mut [u8] {
+                if GROWN.is_empty() {
+                    &mut scratch[..]
+                } else {
+                    &mut GROWN[..]
+                }
+            }
+
+            /// Makes the buffer hold at least `len` bytes, returning its
+            /// offset in memory
+            #[no_mangle]
+            fn scratch_grow(len: u32) -> u32 {
+                let len = len as usize;
+                unsafe {
+                    if len > buffer().len() {
+                        // Leave room for the returned state and value. If
+                        // they turn out larger, the host runs the call again
+                        // with a larger buffer.
+                        GROWN.resize(len + #SCRATCH_SIZE, 0);
+                    }
+                    buffer().as_ptr() as u32
+                }
+            }
+
             #[no_mangle]
             #init_impl
         }
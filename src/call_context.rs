@@ -4,29 +4,127 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use std::mem;
+use std::sync::Arc;
+use std::{cmp, mem};
 
+use dusk_plonk::commitment_scheme::PublicParameters;
 use microkelvin::{BranchRef, BranchRefMut, MaybeArchived};
+use rkyv::AlignedVec;
 use rusk_uplink::{
     ContractId, RawQuery, RawTransaction, ReturnValue, StoreContext,
+    RETURN_BUFFER_FLAG,
 };
 
 use tracing::{trace, trace_span};
-use wasmer::{Exports, ImportObject, Instance, LazyInit, Module, NativeFunc};
+use wasmer::{
+    Exports, ImportObject, Instance, LazyInit, Module, NativeFunc, RuntimeError,
+};
 use wasmer_middlewares::metering::set_remaining_points;
 use wasmer_types::Value;
 
+use crate::contract::Contract;
 use crate::env::Env;
 use crate::gas::{Gas, GasMeter};
 use crate::memory::WasmerMemory;
 use crate::modules::compile_module;
-use crate::resolver::HostImportsResolver;
+use crate::resolver::{HostImportsResolver, NAMESPACES};
+use crate::state::abi::{check_entry, EntryKind};
+use crate::state::contracts::derive_contract_id;
 use crate::state::{Event, NetworkState};
 use crate::{Config, VMError};
 
-const SCRATCH_NAME: &str = "scratch";
+pub(crate) const SCRATCH_NAME: &str = "scratch";
+const SCRATCH_GROW_NAME: &str = "scratch_grow";
+
+/// A write to the network state made during a call, holding what is needed to
+/// revert it.
+enum JournalEntry {
+    /// The previous state of a contract.
+    State(ContractId, Vec<u8>),
+    /// A contract removed from the state.
+    Remove(ContractId, Contract),
+    /// The previous value under a key in a contract's storage.
+    Storage(ContractId, Vec<u8>, Option<Vec<u8>>),
+    /// The previous bytecode of a contract.
+    Code(ContractId, Vec<u8>),
+    /// A contract deployed by `deployer` using its previous `nonce`.
+    Deploy {
+        deployer: ContractId,
+        nonce: u64,
+        id: ContractId,
+    },
+}
+
+/// The outcome of running a contract once.
+enum Run {
+    /// The call returned.
+    Done(ReturnValue),
+    /// The result of the call did not fit the scratch buffer, and the call is
+    /// to be run again with one of the given length.
+    Retry(usize),
+}
+
+/// Writes `state` followed by `data` to the scratch buffer of the contract
+/// at `target`, returning the offset of the buffer in its memory.
+///
+/// Contracts exporting a `scratch_grow` function are asked for a buffer
+/// large enough to hold both, and at least `scratch_len` long. For the others
+/// only the memory following their `scratch` global is checked to be large
+/// enough.
+fn write_scratch(
+    target: ContractId,
+    instance: &Instance,
+    memory: &WasmerMemory,
+    state: &[u8],
+    data: &[u8],
+    scratch_len: usize,
+) -> Result<usize, VMError> {
+    let len = state.len() + data.len();
+    let too_large = || VMError::ArgumentTooLarge(len);
+
+    let offset = match instance
+        .exports
+        .get_native_function::<u32, u32>(SCRATCH_GROW_NAME)
+    {
+        Ok(grow) => {
+            let grow_len = u32::try_from(cmp::max(len, scratch_len))
+                .map_err(|_| too_large())?;
+            grow.call(grow_len)
+                .map_err(|e| CallContext::runtime_error(target, e))?
+                as usize
+        }
+        Err(_) => match instance
+            .exports
+            .get_global(SCRATCH_NAME)
+            .map_err(|_| VMError::InvalidWASMModule)?
+            .get()
+        {
+            Value::I32(ofs) => ofs as usize,
+            _ => return Err(VMError::InvalidWASMModule),
+        },
+    };
+
+    if offset + len > memory.data_size() {
+        return Err(too_large());
+    }
+
+    memory.write(offset as u64, state)?;
+    memory.write((offset + state.len()) as u64, data)?;
+
+    Ok(offset)
+}
+
+/// Returns the length of the scratch buffer a contract asked to be run again
+/// with, if `written` carries the [`RETURN_BUFFER_FLAG`].
+fn retry_len(written: u32) -> Option<usize> {
+    (written & RETURN_BUFFER_FLAG != 0)
+        .then(|| (written & !RETURN_BUFFER_FLAG) as usize)
+}
+
 pub struct StackFrame {
     callee: ContractId,
+    position: usize,
+    kind: EntryKind,
     ret: ReturnValue,
     memory: WasmerMemory,
     gas_meter: GasMeter,
@@ -42,12 +140,16 @@ impl std::fmt::Debug for StackFrame {
 impl StackFrame {
     fn new(
         callee: ContractId,
+        position: usize,
+        kind: EntryKind,
         memory: WasmerMemory,
         gas_meter: GasMeter,
         instance: Instance,
     ) -> StackFrame {
         StackFrame {
             callee,
+            position,
+            kind,
             memory,
             ret: Default::default(),
             gas_meter,
@@ -75,7 +177,9 @@ impl StackFrame {
 pub struct CallContext<'a> {
     state: &'a mut NetworkState,
     stack: Vec<StackFrame>,
+    journal: Vec<JournalEntry>,
     events: Vec<Event>,
+    frames: usize,
     block_height: u64,
     store: StoreContext,
 }
@@ -89,7 +193,9 @@ impl<'a> CallContext<'a> {
         CallContext {
             state,
             stack: vec![],
+            journal: vec![],
             events: vec![],
+            frames: 0,
             block_height,
             store,
         }
@@ -129,101 +235,107 @@ impl<'a> CallContext<'a> {
             stack_index = ?self.stack.len()
         );
 
-        let env = Env::new(self);
-
-        let instance: Instance;
-
-        let r = if let Some(_module) =
-            self.state.modules().get_module_ref(&target).get()
-        {
-            // is this a reserved module call?
-            todo!()
-        // todo bogus error just to fix compilation errors
-        } else {
-            let contract = self.state.get_contract(&target)?;
-            let contract = contract.leaf();
-
-            let bytecode = match contract {
-                MaybeArchived::Memory(m) => m.bytecode(),
-                MaybeArchived::Archived(a) => a.bytecode(&self.store),
-            };
-
-            let module = compile_module(bytecode, self.state.config())?;
-
-            let import_names: Vec<String> =
-                module.imports().map(|i| i.name().to_string()).collect();
-            let mut import_object = ImportObject::new();
-            for namespace_name in ["env", "canon"] {
-                CallContext::register_namespace(
-                    namespace_name,
-                    &env,
-                    &module,
-                    &import_names,
-                    &mut import_object,
-                );
-            }
+        let modules = self.state.modules().clone();
+        if let Some(module) = modules.get_module_ref(&target).get() {
+            let caller = self.module_caller(target);
+            let result = module.execute(query, caller, &self.store, gas_meter);
+            self.module_gas_reconciliation(gas_meter)?;
+            return result;
+        }
 
-            instance = Instance::new(&module, &import_object)?;
-            set_remaining_points(&instance, gas_meter.left());
+        self.run_to_fit(|context, scratch_len| {
+            context.execute_query(target, &query, gas_meter, scratch_len)
+        })
+    }
 
-            let mut memory = WasmerMemory {
-                inner: LazyInit::new(),
-            };
-            memory.init(&instance.exports)?;
+    fn execute_query(
+        &mut self,
+        target: ContractId,
+        query: &RawQuery,
+        gas_meter: &mut GasMeter,
+        scratch_len: usize,
+    ) -> Result<Run, VMError> {
+        let env = Env::new(self);
 
-            self.stack.push(StackFrame::new(
-                target,
-                memory,
-                gas_meter.clone(),
-                instance.clone(),
-            ));
+        let contract = self.state.get_contract(&target)?;
+        let contract = contract.leaf();
 
-            let run_func: NativeFunc<(u32, u32), u32> =
-                instance.exports.get_native_function(query.name())?;
-
-            let buf_offset = if let Value::I32(ofs) = instance
-                .exports
-                .get_global(SCRATCH_NAME)
-                .map_err(|_| VMError::InvalidWASMModule)?
-                .get()
-            {
-                ofs as usize
-            } else {
-                return Err(VMError::InvalidWASMModule);
-            };
-
-            let mut memory = WasmerMemory::new();
-            memory.init(&instance.exports)?;
-
-            // Write the current archived state and the query into contract
-            // scratch buffer
-
-            let (written_state, written_data) =
-                memory.with_mut_slice_from(buf_offset, |mem| {
-                    let state = match contract {
-                        MaybeArchived::Memory(m) => m.state(),
-                        MaybeArchived::Archived(a) => a.state(&self.store),
-                    };
+        let bytecode = match contract {
+            MaybeArchived::Memory(m) => m.bytecode(),
+            MaybeArchived::Archived(a) => a.bytecode(&self.store),
+        };
 
-                    let len = state.len() as usize;
+        let module = compile_module(bytecode, self.state.config())?;
+        check_entry(&module, target, query.name(), EntryKind::Query)?;
+
+        let import_names: Vec<String> =
+            module.imports().map(|i| i.name().to_string()).collect();
+        let mut import_object = ImportObject::new();
+        for namespace_name in NAMESPACES {
+            CallContext::register_namespace(
+                namespace_name,
+                &env,
+                &module,
+                &import_names,
+                &mut import_object,
+            );
+        }
 
-                    mem[0..len].copy_from_slice(state);
+        let instance = Instance::new(&module, &import_object)?;
+        set_remaining_points(&instance, gas_meter.left());
 
-                    let data = query.data();
+        let mut memory = WasmerMemory {
+            inner: LazyInit::new(),
+        };
+        memory.init(&instance.exports)?;
 
-                    mem[len..len + data.len()].copy_from_slice(data);
+        let position = self.next_frame();
+        self.stack.push(StackFrame::new(
+            target,
+            position,
+            EntryKind::Query,
+            memory,
+            gas_meter.clone(),
+            instance.clone(),
+        ));
 
-                    (len, len + data.len())
-                });
+        let run_func: NativeFunc<(u32, u32), u32> =
+            instance.exports.get_native_function(query.name())?;
 
-            let r = run_func.call(written_state as u32, written_data as u32);
+        let mut memory = WasmerMemory::new();
+        memory.init(&instance.exports)?;
 
-            r.map(|result_written| {
-                memory.with_slice_from(buf_offset, |mem| {
-                    ReturnValue::new(&mem[..result_written as usize])
-                })
-            })
+        // Write the current archived state and the query into contract
+        // scratch buffer
+        let state = match contract {
+            MaybeArchived::Memory(m) => m.state(),
+            MaybeArchived::Archived(a) => a.state(&self.store),
         };
+        let data = query.data();
+
+        let buf_offset = write_scratch(
+            target,
+            &instance,
+            &memory,
+            state,
+            data,
+            scratch_len,
+        )?;
+
+        let written_state = state.len();
+        let written_data = written_state + data.len();
+
+        let r = run_func
+            .call(written_state as u32, written_data as u32)
+            .map_err(|e| Self::runtime_error(target, e))
+            .and_then(|result_written| {
+                if let Some(len) = retry_len(result_written) {
+                    return Ok(Run::Retry(len));
+                }
+                memory
+                    .read(buf_offset as u64, result_written as usize)
+                    .map(|result| Run::Done(ReturnValue::new(result)))
+            });
 
         match self.gas_reconciliation() {
             Ok(gas) => *gas_meter = gas,
@@ -238,10 +350,7 @@ impl<'a> CallContext<'a> {
             gas_meter.spent()
         );
 
-        let result =
-            r.map_err(|a| VMError::ContractPanic(target, a.message()))?;
-        self.stack.pop();
-        Ok(result)
+        r
     }
 
     pub fn transact(
@@ -257,108 +366,137 @@ impl<'a> CallContext<'a> {
             stack_index = ?self.stack.len()
         );
 
-        let env = Env::new(self);
-
-        let instance;
-
-        let r = {
-            let config = self.state.config();
-            let mut contract = self.state.get_contract_mut(&target)?;
-            let contract = contract.leaf_mut();
-
-            let module = compile_module(contract.bytecode(), config)?;
-
-            let import_names: Vec<String> =
-                module.imports().map(|i| i.name().to_string()).collect();
-            let mut import_object = ImportObject::new();
-            for namespace_name in ["env", "canon"] {
-                CallContext::register_namespace(
-                    namespace_name,
-                    &env,
-                    &module,
-                    &import_names,
-                    &mut import_object,
-                );
-            }
-            instance = Instance::new(&module, &import_object)?;
-            set_remaining_points(&instance, gas_meter.left());
-
-            let mut memory = WasmerMemory {
-                inner: LazyInit::new(),
-            };
-            memory.init(&instance.exports)?;
+        let modules = self.state.modules().clone();
+        if let Some(module) = modules.get_module_ref(&target).get() {
+            let caller = self.module_caller(target);
+            let result =
+                module.transact(transaction, caller, &self.store, gas_meter);
+            self.module_gas_reconciliation(gas_meter)?;
+            return result;
+        }
 
-            self.stack.push(StackFrame::new(
+        self.run_to_fit(|context, scratch_len| {
+            context.execute_transaction(
                 target,
-                memory,
-                gas_meter.clone(),
-                instance.clone(),
-            ));
-
-            let run_func: NativeFunc<(u32, u32), u64> =
-                instance.exports.get_native_function(transaction.name())?;
-
-            let buf_offset = if let Value::I32(ofs) = instance
-                .exports
-                .get_global(SCRATCH_NAME)
-                .map_err(|_| VMError::InvalidWASMModule)?
-                .get()
-            {
-                ofs as usize
-            } else {
-                return Err(VMError::InvalidWASMModule);
-            };
+                &transaction,
+                gas_meter,
+                scratch_len,
+            )
+        })
+    }
 
-            let mut memory = WasmerMemory::new();
-            memory.init(&instance.exports)?;
+    fn execute_transaction(
+        &mut self,
+        target: ContractId,
+        transaction: &RawTransaction,
+        gas_meter: &mut GasMeter,
+        scratch_len: usize,
+    ) -> Result<Run, VMError> {
+        let env = Env::new(self);
 
-            // Copy the contract state and the transaction into scratch memory
+        let config = self.state.config();
+        let contract = self.state.get_contract(&target)?;
+        let contract = contract.leaf();
 
-            let (written_state, written_data) =
-                memory.with_mut_slice_from(buf_offset, |mem| {
-                    // copy the contract state into scratch memory
-                    let state = contract.state();
+        let bytecode = match contract {
+            MaybeArchived::Memory(m) => m.bytecode(),
+            MaybeArchived::Archived(a) => a.bytecode(&self.store),
+        };
 
-                    let len = state.len();
+        let module = compile_module(bytecode, config)?;
+        check_entry(
+            &module,
+            target,
+            transaction.name(),
+            EntryKind::Transaction,
+        )?;
+
+        let import_names: Vec<String> =
+            module.imports().map(|i| i.name().to_string()).collect();
+        let mut import_object = ImportObject::new();
+        for namespace_name in NAMESPACES {
+            CallContext::register_namespace(
+                namespace_name,
+                &env,
+                &module,
+                &import_names,
+                &mut import_object,
+            );
+        }
+        let instance = Instance::new(&module, &import_object)?;
+        set_remaining_points(&instance, gas_meter.left());
 
-                    mem[..len].copy_from_slice(state);
+        let mut memory = WasmerMemory {
+            inner: LazyInit::new(),
+        };
+        memory.init(&instance.exports)?;
 
-                    let data = transaction.data();
-                    let data_len = data.len();
+        let position = self.next_frame();
+        self.stack.push(StackFrame::new(
+            target,
+            position,
+            EntryKind::Transaction,
+            memory,
+            gas_meter.clone(),
+            instance.clone(),
+        ));
 
-                    mem[len..len + data_len].copy_from_slice(data);
+        let run_func: NativeFunc<(u32, u32), u64> =
+            instance.exports.get_native_function(transaction.name())?;
 
-                    (len, len + data_len)
-                });
+        let mut memory = WasmerMemory::new();
+        memory.init(&instance.exports)?;
 
-            // note to self: refactor plz, this can be done with bit-shifting
-            fn separate_tuple(tuple: u64) -> (u32, u32) {
-                let bytes = tuple.to_le_bytes();
-                let mut a = [0u8; 4];
-                let mut b = [0u8; 4];
-                a.copy_from_slice(&bytes[..4]);
-                b.copy_from_slice(&bytes[4..]);
-                (u32::from_le_bytes(a), u32::from_le_bytes(b))
-            }
+        // Copy the contract state and the transaction into scratch memory
+        let state = match contract {
+            MaybeArchived::Memory(m) => m.state(),
+            MaybeArchived::Archived(a) => a.state(&self.store),
+        };
+        let data = transaction.data();
 
-            let r = run_func.call(written_state as u32, written_data as u32);
+        let buf_offset = write_scratch(
+            target,
+            &instance,
+            &memory,
+            state,
+            data,
+            scratch_len,
+        )?;
+
+        let written_state = state.len();
+        let written_data = written_state + data.len();
+
+        // note to self: refactor plz, this can be done with bit-shifting
+        fn separate_tuple(tuple: u64) -> (u32, u32) {
+            let bytes = tuple.to_le_bytes();
+            let mut a = [0u8; 4];
+            let mut b = [0u8; 4];
+            a.copy_from_slice(&bytes[..4]);
+            b.copy_from_slice(&bytes[4..]);
+            (u32::from_le_bytes(a), u32::from_le_bytes(b))
+        }
 
-            r.map(|result| {
+        let r = run_func
+            .call(written_state as u32, written_data as u32)
+            .map_err(|e| Self::runtime_error(target, e))
+            .and_then(|result| {
                 let (state_written, result_written) = separate_tuple(result);
-
-                memory.with_slice_from(buf_offset, |mem| {
-                    let new_state = &mem[..state_written as usize];
-
-                    contract.set_state(new_state);
-
-                    let result_len = result_written - state_written;
-                    ReturnValue::with_state(
-                        &mem[state_written as usize..][..result_len as usize],
-                        &mem[..state_written as usize],
-                    )
-                })
-            })
-        };
+                if let Some(len) = retry_len(state_written) {
+                    return Ok(Run::Retry(len));
+                }
+                let result_len = result_written
+                    .checked_sub(state_written)
+                    .ok_or(VMError::InvalidData)?;
+
+                let buf_offset = buf_offset as u64;
+                let state = memory.read(buf_offset, state_written as usize)?;
+                let data = memory.read(
+                    buf_offset + state_written as u64,
+                    result_len as usize,
+                )?;
+
+                Ok(Run::Done(ReturnValue::with_state(data, state)))
+            });
 
         match self.gas_reconciliation() {
             Ok(gas) => *gas_meter = gas,
@@ -373,19 +511,321 @@ impl<'a> CallContext<'a> {
             gas_meter.spent()
         );
 
-        let result =
-            r.map_err(|a| VMError::ContractPanic(target, a.message()))?;
-        self.stack.pop();
-        Ok(result)
+        let result = match r? {
+            Run::Done(result) => result,
+            retry => return Ok(retry),
+        };
+
+        // The new state is only written once the whole call succeeded, and
+        // is journaled so it can be reverted should any caller fail. A
+        // contract that destroyed itself has no state left to write.
+        if self.state.get_contract(&target).is_ok() {
+            self.set_state(target, result.state())?;
+        }
+
+        Ok(Run::Done(result))
+    }
+
+    /// Runs a contract through `run` until its result fits the scratch
+    /// buffer, undoing whatever each run cut short wrote or emitted. The gas
+    /// spent by those runs stays spent.
+    fn run_to_fit<F>(&mut self, mut run: F) -> Result<ReturnValue, VMError>
+    where
+        F: FnMut(&mut Self, usize) -> Result<Run, VMError>,
+    {
+        let depth = self.stack.len();
+        let checkpoint = self.journal.len();
+        let events = self.events.len();
+        let frames = self.frames;
+
+        let mut scratch_len = 0;
+        let result = loop {
+            match run(self, scratch_len) {
+                Ok(Run::Done(result)) => break Ok(result),
+                Ok(Run::Retry(len)) if len > scratch_len => {
+                    self.stack.truncate(depth);
+                    self.events.truncate(events);
+                    self.revert(checkpoint)?;
+                    self.frames = frames;
+                    scratch_len = len;
+                }
+                Ok(Run::Retry(len)) => {
+                    break Err(VMError::ArgumentTooLarge(len))
+                }
+                Err(error) => break Err(error),
+            }
+        };
+
+        self.unwind(depth, checkpoint, events, result)
+    }
+
+    /// Maps an error returned by running the contract at `target`.
+    ///
+    /// Errors raised by host functions - including the ones of failed nested
+    /// calls - are propagated as they are, anything else is considered a
+    /// panic of the contract itself.
+    fn runtime_error(target: ContractId, error: RuntimeError) -> VMError {
+        match error.downcast::<VMError>() {
+            Ok(vm_error) => vm_error,
+            Err(error) => VMError::ContractPanic(target, error.message()),
+        }
+    }
+
+    /// Unwinds the stack back to `depth`. If the call failed, every state
+    /// write journaled since `checkpoint` is reverted, and the events emitted
+    /// past the first `events` - by the call or the calls it made - are
+    /// discarded.
+    fn unwind(
+        &mut self,
+        depth: usize,
+        checkpoint: usize,
+        events: usize,
+        result: Result<ReturnValue, VMError>,
+    ) -> Result<ReturnValue, VMError> {
+        self.stack.truncate(depth);
+
+        if result.is_err() {
+            self.events.truncate(events);
+            self.revert(checkpoint)?;
+        }
+
+        result
     }
 
+    /// Returns the point [`rollback`] undoes the writes and events of the
+    /// following calls to.
+    ///
+    /// [`rollback`]: CallContext::rollback
+    pub(crate) fn checkpoint(&self) -> (usize, usize) {
+        (self.journal.len(), self.events.len())
+    }
+
+    /// Undoes the writes made and the events emitted since `checkpoint`, as
+    /// if the calls in between never happened.
+    pub(crate) fn rollback(
+        &mut self,
+        (checkpoint, events): (usize, usize),
+    ) -> Result<(), VMError> {
+        self.events.truncate(events);
+        self.revert(checkpoint)
+    }
+
+    /// Sets the state of the contract at `id`, journaling its previous
+    /// state.
+    fn set_state(
+        &mut self,
+        id: ContractId,
+        state: &[u8],
+    ) -> Result<(), VMError> {
+        let mut contract = self.state.get_contract_mut(&id)?;
+        let contract = contract.leaf_mut();
+
+        self.journal
+            .push(JournalEntry::State(id, contract.state().to_vec()));
+        contract.set_state(state);
+
+        Ok(())
+    }
+
+    /// Removes the contract at `id` from the state, journaling it so it can
+    /// be restored.
+    ///
+    /// Fails if the current callee is being queried.
+    pub fn remove_contract(&mut self, id: ContractId) -> Result<(), VMError> {
+        if self.in_query() {
+            return Err(VMError::StateChangeInQuery(*self.callee()));
+        }
+
+        let contract = self.state.remove_contract(&id)?;
+        self.journal.push(JournalEntry::Remove(id, contract));
+        Ok(())
+    }
+
+    /// Returns the value stored under `key` in the storage of the current
+    /// callee.
+    pub fn storage_get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, VMError> {
+        let callee = *self.callee();
+        let contract = self.state.get_contract(&callee)?;
+
+        let value = match contract.leaf() {
+            MaybeArchived::Memory(m) => m.storage_get(key),
+            MaybeArchived::Archived(a) => a.storage_get(key, &self.store),
+        };
+
+        Ok(value)
+    }
+
+    /// Stores `value` under `key` in the storage of the current callee,
+    /// journaling the previous value.
+    ///
+    /// Fails if the callee is being queried.
+    pub fn storage_set(
+        &mut self,
+        key: &[u8],
+        value: Vec<u8>,
+    ) -> Result<(), VMError> {
+        let callee = *self.callee();
+        if self.in_query() {
+            return Err(VMError::StateChangeInQuery(callee));
+        }
+
+        let mut contract = self.state.get_contract_mut(&callee)?;
+        let previous = contract.leaf_mut().storage_set(key, value);
+
+        self.journal.push(JournalEntry::Storage(
+            callee,
+            key.to_vec(),
+            previous,
+        ));
+        Ok(())
+    }
+
+    /// Removes the value stored under `key` in the storage of the current
+    /// callee, journaling it. Returns whether there was a value to remove.
+    ///
+    /// Fails if the callee is being queried.
+    pub fn storage_remove(&mut self, key: &[u8]) -> Result<bool, VMError> {
+        let callee = *self.callee();
+        if self.in_query() {
+            return Err(VMError::StateChangeInQuery(callee));
+        }
+
+        let mut contract = self.state.get_contract_mut(&callee)?;
+        let previous = contract.leaf_mut().storage_remove(key);
+
+        let removed = previous.is_some();
+        if removed {
+            self.journal.push(JournalEntry::Storage(
+                callee,
+                key.to_vec(),
+                previous,
+            ));
+        }
+        Ok(removed)
+    }
+
+    /// Reverts all the journaled writes made after `checkpoint`, in reverse
+    /// order.
+    fn revert(&mut self, checkpoint: usize) -> Result<(), VMError> {
+        while self.journal.len() > checkpoint {
+            match self.journal.pop().expect("Journal should not be empty") {
+                JournalEntry::State(id, state) => {
+                    let mut contract = self.state.get_contract_mut(&id)?;
+                    contract.leaf_mut().set_state(&state);
+                }
+                JournalEntry::Remove(id, contract) => {
+                    self.state.deploy_with_id(id, contract)?;
+                }
+                JournalEntry::Storage(id, key, value) => {
+                    let mut contract = self.state.get_contract_mut(&id)?;
+                    let contract = contract.leaf_mut();
+                    match value {
+                        Some(value) => contract.storage_set(&key, value),
+                        None => contract.storage_remove(&key),
+                    };
+                }
+                JournalEntry::Code(id, code) => {
+                    let mut contract = self.state.get_contract_mut(&id)?;
+                    contract.leaf_mut().set_code(code);
+                }
+                JournalEntry::Deploy {
+                    deployer,
+                    nonce,
+                    id,
+                } => {
+                    self.state.remove_contract(&id)?;
+                    let mut contract =
+                        self.state.get_contract_mut(&deployer)?;
+                    contract.leaf_mut().set_nonce(nonce);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Deploys a contract on behalf of the current callee, returning its
+    /// id.
+    ///
+    /// The id is derived from the deployer's id and its nonce, which is
+    /// incremented with every deployment.
+    pub fn deploy(
+        &mut self,
+        code: Vec<u8>,
+        state: Vec<u8>,
+    ) -> Result<ContractId, VMError> {
+        let deployer = *self.callee();
+        if self.in_query() {
+            return Err(VMError::StateChangeInQuery(deployer));
+        }
+
+        let nonce = self.state.get_contract_mut(&deployer)?.leaf_mut().nonce();
+
+        let id = derive_contract_id(&deployer, nonce);
+        self.state
+            .deploy_with_id(id, Contract::from_raw(state, code))?;
+
+        let mut contract = self.state.get_contract_mut(&deployer)?;
+        contract.leaf_mut().set_nonce(nonce + 1);
+
+        self.journal.push(JournalEntry::Deploy {
+            deployer,
+            nonce,
+            id,
+        });
+
+        Ok(id)
+    }
+
+    /// Replaces the bytecode of the current callee, keeping its state,
+    /// storage and nonce.
+    ///
+    /// The current call carries on with the previous bytecode, and the new
+    /// one is used from the next call on.
+    pub fn upgrade(&mut self, code: Vec<u8>) -> Result<(), VMError> {
+        let callee = *self.callee();
+        if self.in_query() {
+            return Err(VMError::StateChangeInQuery(callee));
+        }
+
+        compile_module(&code, self.state.config())?;
+
+        let mut contract = self.state.get_contract_mut(&callee)?;
+        let previous = contract.leaf_mut().set_code(code);
+
+        self.journal.push(JournalEntry::Code(callee, previous));
+
+        Ok(())
+    }
+
+    /// Returns true if the current call was made by a query, directly or
+    /// through other calls.
+    fn in_query(&self) -> bool {
+        self.stack
+            .iter()
+            .any(|frame| frame.kind == EntryKind::Query)
+    }
+
+    /// Returns the position of the next frame pushed onto the stack, in
+    /// order of calls.
+    fn next_frame(&mut self) -> usize {
+        let position = self.frames;
+        self.frames += 1;
+        position
+    }
+
+    /// Pushes an event emitted by the frame on top of the stack.
     pub fn push_event(
         &mut self,
         origin: ContractId,
         name: String,
-        data: Vec<u8>,
+        topics: Vec<[u8; 32]>,
+        data: AlignedVec,
     ) {
-        self.events.push(Event::new(origin, name, data));
+        let depth = self.stack.len() - 1;
+        let frame = self.top().position;
+
+        self.events
+            .push(Event::new(origin, name, topics, data, depth, frame));
     }
 
     pub fn take_events(&mut self) -> Vec<Event> {
@@ -420,6 +860,10 @@ impl<'a> CallContext<'a> {
         self.state.config()
     }
 
+    pub fn public_parameters(&self) -> Option<Arc<PublicParameters>> {
+        self.state.public_parameters().cloned()
+    }
+
     pub fn top(&self) -> &StackFrame {
         self.stack.last().expect("Stack should not be empty")
     }
@@ -457,13 +901,36 @@ impl<'a> CallContext<'a> {
         Ok(())
     }
 
+    /// The caller seen by a host module called at `target`.
+    fn module_caller(&self, target: ContractId) -> ContractId {
+        self.stack
+            .last()
+            .map(|frame| frame.callee)
+            .unwrap_or(target)
+    }
+
+    /// Charge the gas spent by a host module to the calling frame, if any.
+    fn module_gas_reconciliation(
+        &mut self,
+        gas_meter: &GasMeter,
+    ) -> Result<(), VMError> {
+        if !self.stack.is_empty() {
+            self.charge_gas(gas_meter.spent())?;
+        }
+        Ok(())
+    }
+
     /// Reconcile the gas usage across the stack.
     fn gas_reconciliation(&mut self) -> Result<GasMeter, VMError> {
+        // The topmost meter may have been exhausted, in which case an error is
+        // returned but its gas still needs to be charged to the parent.
+        let gas_meter = self.gas_meter().map(|gas_meter| gas_meter.clone());
+
         // If there is more than one [`StackFrame`] on the stack, then the
         // gas needs to be reconciled.
         if self.stack.len() > 1 {
             let len = self.stack.len() - 2;
-            let spent = self.gas_meter()?.spent();
+            let spent = self.top().gas_meter.spent();
             let parent = &mut self.stack[len];
             let parent_meter = &mut parent.gas_meter;
             let parent_instance = &parent.instance;
@@ -477,6 +944,6 @@ impl<'a> CallContext<'a> {
             // how to handle the gas consumption inside native calls.
             parent_meter.update(parent_instance, spent)?;
         }
-        Ok(self.gas_meter()?.clone())
+        gas_meter
     }
 }
@@ -127,16 +127,63 @@ pub struct HostCosts {
     pub callee: Gas,
     pub caller: Gas,
     pub debug: Gas,
+    /// Cost per byte of deployed bytecode
+    pub deploy: Gas,
+    /// Cost per call to `emit`
     pub emit: Gas,
+    /// Cost per topic indexing an emitted event
+    pub emit_per_topic: Gas,
     pub gas_consumed: Gas,
     pub gas_left: Gas,
     pub panic: Gas,
     pub get: Gas,
     /// Cost per byte stored
     pub put: Gas,
+    /// Cost per call to the blake2b-256 `hash`
     pub hash: Gas,
+    /// Cost per byte hashed with `hash`
+    pub hash_per_byte: Gas,
     pub query: Gas,
     pub transact: Gas,
+    pub self_destruct: Gas,
+    /// Cost per byte of bytecode a contract is upgraded to
+    pub upgrade: Gas,
+    /// Cost per call to `storage_get`
+    pub storage_get: Gas,
+    /// Cost per byte of key and value read from contract storage
+    pub storage_get_per_byte: Gas,
+    /// Cost per call to `storage_set`
+    pub storage_set: Gas,
+    /// Cost per byte of key and value written to contract storage
+    pub storage_set_per_byte: Gas,
+    /// Cost per call to `storage_remove`
+    pub storage_remove: Gas,
+    /// Cost per byte of key removed from contract storage
+    pub storage_remove_per_byte: Gas,
+    /// Cost per call to `sha256`
+    pub sha256: Gas,
+    /// Cost per byte hashed with `sha256`
+    pub sha256_per_byte: Gas,
+    /// Cost per call to `keccak256`
+    pub keccak256: Gas,
+    /// Cost per byte hashed with `keccak256`
+    pub keccak256_per_byte: Gas,
+    /// Cost per call to `poseidon_hash`
+    pub poseidon_hash: Gas,
+    /// Cost per byte of scalars hashed with `poseidon_hash`
+    pub poseidon_hash_per_byte: Gas,
+    /// Cost per call to `schnorr_verify`
+    pub schnorr_verify: Gas,
+    /// Cost per call to `bls_verify`
+    pub bls_verify: Gas,
+    /// Cost per byte of message verified with `bls_verify`
+    pub bls_verify_per_byte: Gas,
+    /// Cost per call to `verify_proof`
+    pub verify_proof: Gas,
+    /// Cost per byte of verifier data and proof passed to `verify_proof`
+    pub verify_proof_per_byte: Gas,
+    /// Cost per public input passed to `verify_proof`
+    pub verify_proof_per_input: Gas,
 }
 
 impl HostCosts {
@@ -147,15 +194,38 @@ impl HostCosts {
             callee: 1,
             caller: 1,
             debug: 1,
+            deploy: 1,
             emit: 1,
+            emit_per_topic: 1,
             gas_consumed: 1,
             gas_left: 1,
             panic: 1,
             get: 1,
             put: 1,
             hash: 1,
+            hash_per_byte: 1,
             query: 1,
             transact: 1,
+            self_destruct: 1,
+            upgrade: 1,
+            storage_get: 1,
+            storage_get_per_byte: 1,
+            storage_set: 1,
+            storage_set_per_byte: 1,
+            storage_remove: 1,
+            storage_remove_per_byte: 1,
+            sha256: 1,
+            sha256_per_byte: 1,
+            keccak256: 1,
+            keccak256_per_byte: 1,
+            poseidon_hash: 1,
+            poseidon_hash_per_byte: 1,
+            schnorr_verify: 1,
+            bls_verify: 1,
+            bls_verify_per_byte: 1,
+            verify_proof: 1,
+            verify_proof_per_byte: 1,
+            verify_proof_per_input: 1,
         }
     }
 }
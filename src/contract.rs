@@ -5,14 +5,53 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use bytecheck::CheckBytes;
+use dusk_hamt::{Hamt, Lookup};
 use microkelvin::{
-    Child, ChildMut, Compound, Link, MaybeStored, OffsetLen, StoreSerializer,
+    Annotation, BranchRef, Child, ChildMut, Compound, Link, MaybeArchived,
+    MaybeStored, OffsetLen, StoreSerializer,
 };
 use rkyv::{ser::Serializer, Archive, Deserialize, Serialize};
 
-use rusk_uplink::StoreContext;
+use rusk_uplink::{code_hash, StoreContext};
 pub use rusk_uplink::{ContractId, ContractState};
 
+use crate::state::contracts::HashAnnotation;
+use crate::state::hash::hash;
+
+/// Key of a value in a contract's storage, derived from the key bytes given
+/// by the contract.
+#[derive(
+    PartialEq,
+    Eq,
+    Hash,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Archive,
+    Serialize,
+    Deserialize,
+    CheckBytes,
+)]
+#[archive(as = "Self")]
+pub struct StorageKey([u8; 32]);
+
+impl StorageKey {
+    /// Derives the storage key for the given bytes
+    pub fn new(key: &[u8]) -> Self {
+        Self(hash(key))
+    }
+}
+
+impl AsRef<[u8]> for StorageKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+pub(crate) type StorageTree =
+    Hamt<StorageKey, Vec<u8>, HashAnnotation, OffsetLen>;
+
 #[derive(Archive, Clone, Serialize, Deserialize)]
 #[archive_attr(derive(CheckBytes))]
 pub struct ContractData(Vec<u8>);
@@ -53,6 +92,9 @@ impl<A, I> Compound<A, I> for ContractData {
 pub struct Contract {
     state: Link<ContractData, (), OffsetLen>,
     code: Link<ContractData, (), OffsetLen>,
+    code_hash: [u8; 32],
+    storage: StorageTree,
+    nonce: u64,
 }
 
 impl Contract {
@@ -71,10 +113,28 @@ impl Contract {
 
         let state_vec = ser.spill_bytes(|bytes| Vec::from(bytes));
 
-        let state = Link::new(ContractData(state_vec));
-        let code = Link::new(ContractData(code.into()));
+        Self::from_raw(state_vec, code)
+    }
+
+    /// Create a new Contract with already serialized initial state and code
+    pub(crate) fn from_raw<State, Code>(state: State, code: Code) -> Self
+    where
+        State: Into<Vec<u8>>,
+        Code: Into<Vec<u8>>,
+    {
+        let code = code.into();
+        let code_hash = code_hash(&code);
+
+        let state = Link::new(ContractData(state.into()));
+        let code = Link::new(ContractData(code));
 
-        Contract { state, code }
+        Contract {
+            state,
+            code,
+            code_hash,
+            storage: StorageTree::new(),
+            nonce: 0,
+        }
     }
 
     /// Update the contract's state
@@ -85,6 +145,16 @@ impl Contract {
         s.0.extend_from_slice(state);
     }
 
+    /// Replaces the contract's bytecode, returning the previous one
+    pub(crate) fn set_code(&mut self, code: Vec<u8>) -> Vec<u8> {
+        let previous = self.bytecode().to_vec();
+
+        self.code_hash = code_hash(&code);
+        self.code = Link::new(ContractData(code));
+
+        previous
+    }
+
     /// Returns a slice to the contract's bytecode
     pub fn bytecode(&self) -> &[u8] {
         match self.code.inner() {
@@ -93,6 +163,12 @@ impl Contract {
         }
     }
 
+    /// Returns the hash of the contract's bytecode, computed once when the
+    /// contract is created
+    pub fn code_hash(&self) -> &[u8; 32] {
+        &self.code_hash
+    }
+
     /// Returns a slice to the contract's state
     pub fn state(&self) -> &[u8] {
         match self.state.inner() {
@@ -100,6 +176,42 @@ impl Contract {
             MaybeStored::Stored(s) => s.inner().as_ref(),
         }
     }
+
+    /// Returns the value stored under `key` in the contract's storage
+    pub fn storage_get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        storage_get(&self.storage, key)
+    }
+
+    /// Stores `value` under `key` in the contract's storage, returning the
+    /// previous value
+    pub(crate) fn storage_set(
+        &mut self,
+        key: &[u8],
+        value: Vec<u8>,
+    ) -> Option<Vec<u8>> {
+        self.storage.insert(StorageKey::new(key), value)
+    }
+
+    /// Removes the value stored under `key` in the contract's storage,
+    /// returning it
+    pub(crate) fn storage_remove(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+        self.storage.remove(&StorageKey::new(key))
+    }
+
+    /// Returns the root hash of the contract's storage
+    pub fn storage_root(&self) -> [u8; 32] {
+        HashAnnotation::from_node(&self.storage).into()
+    }
+
+    /// Returns the number of contracts deployed by this contract
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    /// Set the number of contracts deployed by this contract
+    pub(crate) fn set_nonce(&mut self, nonce: u64) {
+        self.nonce = nonce;
+    }
 }
 
 impl ArchivedContract {
@@ -108,8 +220,67 @@ impl ArchivedContract {
         &store.get(self.code.ident()).0
     }
 
+    /// Returns the hash of the contract's bytecode
+    pub fn code_hash(&self) -> &[u8; 32] {
+        &self.code_hash
+    }
+
     /// Returns the identity of the contract's state in the store
     pub fn state<'a>(&self, store: &'a StoreContext) -> &'a [u8] {
         &store.get(self.state.ident()).0
     }
+
+    /// Returns the value stored under `key` in the contract's storage,
+    /// loading the nodes on its path from the store
+    pub fn storage_get(
+        &self,
+        key: &[u8],
+        store: &StoreContext,
+    ) -> Option<Vec<u8>> {
+        let storage: StorageTree = self
+            .storage
+            .deserialize(&mut store.clone())
+            .expect("Infallible");
+        storage_get(&storage, key)
+    }
+}
+
+fn storage_get(storage: &StorageTree, key: &[u8]) -> Option<Vec<u8>> {
+    storage
+        .get(&StorageKey::new(key))
+        .map(|branch| match branch.leaf() {
+            MaybeArchived::Memory(value) => value.clone(),
+            MaybeArchived::Archived(value) => value.to_vec(),
+        })
+}
+
+/// A contract as persisted with [`HASH_VERSION`] `0`, before contracts
+/// carried their storage, nonce and code hash.
+///
+/// [`HASH_VERSION`]: crate::HASH_VERSION
+#[derive(Archive, Clone, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub(crate) struct LegacyContract {
+    state: Link<ContractData, (), OffsetLen>,
+    code: Link<ContractData, (), OffsetLen>,
+}
+
+impl LegacyContract {
+    /// Returns a slice to the contract's state
+    pub fn state(&self) -> &[u8] {
+        match self.state.inner() {
+            MaybeStored::Memory(m) => m.as_ref(),
+            MaybeStored::Stored(s) => s.inner().as_ref(),
+        }
+    }
+
+    /// Migrates the contract to the current layout, with an empty storage
+    /// and a nonce of zero, neither of which existed before.
+    pub fn migrate(&self) -> Contract {
+        let code = match self.code.inner() {
+            MaybeStored::Memory(m) => m.as_ref().to_vec(),
+            MaybeStored::Stored(s) => s.inner().as_ref().to_vec(),
+        };
+        Contract::from_raw(self.state(), code)
+    }
 }
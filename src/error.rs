@@ -6,9 +6,10 @@
 
 use crate::gas;
 use crate::modules;
+use crate::state::abi::EntryKind;
 use crate::state::persist::PersistError;
 
-use rusk_uplink::ContractId;
+use rusk_uplink::{CallError, ContractId};
 use thiserror::Error;
 use wasmer_vm::TrapCode;
 
@@ -33,12 +34,61 @@ pub enum VMError {
     /// Contract could not be found in the state
     #[error("Contract {0} could not be found in the state")]
     UnknownContract(ContractId),
+    /// Contract already exists
+    #[error("Contract {0} already exists in the state")]
+    ContractAlreadyExists(ContractId),
+    /// The contract exports no query or transaction with the given name
+    #[error("Contract {contract} has no method named `{name}`")]
+    UnknownMethod {
+        /// The called contract
+        contract: ContractId,
+        /// Name of the method
+        name: String,
+    },
+    /// A transaction was called as a query, or a query as a transaction
+    #[error("Method `{name}` of contract {contract} is not a {expected}")]
+    MethodKindMismatch {
+        /// The called contract
+        contract: ContractId,
+        /// Name of the method
+        name: String,
+        /// The kind of method the call expected
+        expected: EntryKind,
+    },
+    /// A contract tried to change the state while being queried
+    #[error("Contract {0} cannot change the state during a query")]
+    StateChangeInQuery(ContractId),
+    /// Argument too large to be passed to a contract
+    #[error("Argument of {0} bytes is too large to be passed to the contract")]
+    ArgumentTooLarge(usize),
+    /// Access to contract memory out of its bounds
+    #[error("Memory access of {len} bytes at {offset} is out of bounds")]
+    MemoryAccessOutOfBounds {
+        /// Offset of the access
+        offset: u64,
+        /// Length of the access
+        len: usize,
+    },
+    /// No public parameters were set to verify proofs with
+    #[error("No public parameters set to verify proofs")]
+    MissingPublicParameters,
     /// Persistence error
     #[error(transparent)]
     PersistenceError(#[from] PersistError),
     /// Invalid WASM module
     #[error("Invalid WASM module")]
     InvalidWASMModule,
+    /// The module imports a function the host does not provide
+    #[error("Unknown import {namespace}::{name}")]
+    UnknownImport {
+        /// Namespace of the import
+        namespace: String,
+        /// Name of the import
+        name: String,
+    },
+    /// The module does not export something required by the host
+    #[error("The module does not export the required `{0}`")]
+    MissingExport(String),
     /// WASMER export error
     #[error(transparent)]
     WasmerExportError(#[from] wasmer::ExportError),
@@ -56,6 +106,24 @@ pub enum VMError {
     WasmerTrap(TrapCode),
 }
 
+impl VMError {
+    /// Returns the error as seen by a contract whose call to another contract
+    /// failed with it.
+    pub(crate) fn to_call_error(&self) -> CallError {
+        match self {
+            VMError::ContractPanic(_, msg) => CallError::Panic(msg.clone()),
+            VMError::OutOfGas => CallError::OutOfGas,
+            VMError::UnknownContract(_) => CallError::UnknownContract,
+            VMError::UnknownMethod { .. } => CallError::UnknownMethod,
+            VMError::MethodKindMismatch { .. } => CallError::MethodKindMismatch,
+            VMError::InvalidData | VMError::InvalidUtf8 => {
+                CallError::InvalidData
+            }
+            error => CallError::Panic(error.to_string()),
+        }
+    }
+}
+
 impl From<gas::GasError> for VMError {
     fn from(_: gas::GasError) -> Self {
         // Currently the only gas error is `GasLimitExceeded`
@@ -23,10 +23,19 @@ mod ops;
 mod resolver;
 mod state;
 
+pub use dusk_plonk;
 pub use rusk_uplink;
 
 pub use config::{Config, HostCosts, OpCosts};
 pub use contract::{Contract, ContractId};
 pub use error::VMError;
 pub use gas::{Gas, GasMeter};
-pub use state::NetworkState;
+pub use modules::{HostModule, HostModules};
+pub use rusk_uplink::StateProof;
+pub use state::abi::{AbiEntry, ContractAbi, EntryKind, ABI_SECTION_NAME};
+pub use state::bloom::{Bloom, BLOOM_SIZE};
+pub use state::contracts::HASH_VERSION;
+pub use state::diff::ContractChange;
+pub use state::iter::{ContractInfo, ContractsIter};
+pub use state::persist::PersistEntry;
+pub use state::{Event, NetworkState, Receipt};
@@ -4,6 +4,8 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use std::ops::Range;
+
 use crate::VMError;
 use wasmer::{LazyInit, Memory};
 
@@ -27,13 +29,16 @@ impl WasmerMemory {
         Ok(())
     }
 
+    /// Size of the memory in bytes
+    pub fn data_size(&self) -> usize {
+        unsafe { self.inner.get_unchecked().data_size() as usize }
+    }
+
     /// Read bytes from memory at a given offset and length
     pub fn read(&self, offset: u64, length: usize) -> Result<&[u8], VMError> {
-        let offset = offset as usize;
-        Ok(unsafe {
-            &self.inner.get_unchecked().data_unchecked()
-                [offset..(offset + length)]
-        })
+        let data = unsafe { self.inner.get_unchecked().data_unchecked() };
+        let range = checked_range(offset, length, data.len())?;
+        Ok(&data[range])
     }
 
     /// Write bytes into memory at a given offset
@@ -42,36 +47,28 @@ impl WasmerMemory {
         offset: u64,
         bytes: impl AsRef<[u8]>,
     ) -> Result<(), VMError> {
-        let offset = offset as usize;
         let slice = bytes.as_ref();
-        unsafe {
-            self.inner.get_unchecked().data_unchecked_mut()
-                [offset..(offset + slice.len())]
-                .copy_from_slice(slice);
-        }
+        let data = unsafe { self.inner.get_unchecked().data_unchecked_mut() };
+        let range = checked_range(offset, slice.len(), data.len())?;
+        data[range].copy_from_slice(slice);
         Ok(())
     }
+}
 
-    /// Write bytes into memory at a given offset
-    pub fn with_mut_slice_from<F, R>(&mut self, offset: usize, closure: F) -> R
-    where
-        F: Fn(&mut [u8]) -> R,
-    {
-        unsafe {
-            let slice =
-                &mut self.inner.get_unchecked().data_unchecked_mut()[offset..];
-            closure(slice)
-        }
-    }
+/// Returns the range of `len` bytes at `offset`, if it fits in a memory of
+/// the given `size`.
+fn checked_range(
+    offset: u64,
+    len: usize,
+    size: usize,
+) -> Result<Range<usize>, VMError> {
+    let out_of_bounds = || VMError::MemoryAccessOutOfBounds { offset, len };
 
-    /// Get a reference into memory
-    pub fn with_slice_from<F, R>(&self, ofs: usize, mut closure: F) -> R
-    where
-        F: FnMut(&[u8]) -> R,
-    {
-        unsafe {
-            let slice = self.inner.get_unchecked().data_unchecked();
-            closure(&slice[ofs..])
-        }
+    let start = usize::try_from(offset).map_err(|_| out_of_bounds())?;
+    let end = start.checked_add(len).ok_or_else(out_of_bounds)?;
+
+    if end > size {
+        return Err(out_of_bounds());
     }
+    Ok(start..end)
 }
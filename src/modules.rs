@@ -4,12 +4,14 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use std::cell::{Ref, RefCell};
 use std::collections::HashMap;
-use std::rc::Rc;
+use std::sync::{Arc, RwLock, RwLockReadGuard};
 
+use crate::call_context::SCRATCH_NAME;
 use crate::compiler::WasmerCompiler;
 use crate::config::{config_hash, Config};
+use crate::gas::GasMeter;
+use crate::resolver::{HostImportsResolver, NAMESPACES};
 use crate::state::hash::hash;
 use crate::VMError;
 
@@ -17,14 +19,52 @@ use cached::cached_key_result;
 use cached::TimedSizedCache;
 use thiserror::Error;
 use tracing::trace;
-use wasmer::Module;
+use wasmer::{ExternType, Module, Type};
 
 pub use rusk_uplink::{ContractId, ContractState};
-
-pub trait HostModule {
-    fn execute(&self);
-
+use rusk_uplink::{RawQuery, RawTransaction, ReturnValue, StoreContext};
+
+/// A contract implemented natively by the host.
+///
+/// Host modules are reachable at their [`module_id`] - usually one of the
+/// [`ContractId::reserved`] addresses - both from the host API and from other
+/// contracts, and take precedence over any contract deployed at the same id.
+///
+/// Modules are shared by all the copies of a [`NetworkState`], which may be
+/// queried from several threads at once.
+///
+/// [`module_id`]: HostModule::module_id
+/// [`NetworkState`]: crate::NetworkState
+pub trait HostModule: Send + Sync {
+    /// Returns the id under which the module is reachable.
     fn module_id(&self) -> ContractId;
+
+    /// Execute a query against the module, returning the archived result.
+    ///
+    /// Any gas consumed should be charged to `gas_meter`, and will be
+    /// accounted for in the calling contract.
+    fn execute(
+        &self,
+        query: RawQuery,
+        caller: ContractId,
+        store: &StoreContext,
+        gas_meter: &mut GasMeter,
+    ) -> Result<ReturnValue, VMError>;
+
+    /// Apply a transaction to the module, returning the archived result.
+    ///
+    /// Host modules hold no state, so by default a transaction is executed
+    /// in the same way as a query with the same name and data.
+    fn transact(
+        &self,
+        transaction: RawTransaction,
+        caller: ContractId,
+        store: &StoreContext,
+        gas_meter: &mut GasMeter,
+    ) -> Result<ReturnValue, VMError> {
+        let query = RawQuery::from(transaction.data(), transaction.name());
+        self.execute(query, caller, store, gas_meter)
+    }
 }
 
 type BoxedHostModule = Box<dyn HostModule>;
@@ -37,6 +77,58 @@ pub fn compile_module(
     get_or_create_module(bytecode, config)
 }
 
+/// Name of the memory every module is required to export.
+const MEMORY_NAME: &str = "memory";
+
+/// Checks that the module only imports existing host functions, with the
+/// right signatures, and exports the memory and scratch buffer used to
+/// exchange data with the host.
+fn validate_module(module: &Module) -> Result<(), VMError> {
+    for import in module.imports() {
+        trace!("Module imports {}::{}", import.module(), import.name());
+
+        let known = NAMESPACES.contains(&import.module())
+            && match import.ty() {
+                ExternType::Function(ty) => {
+                    HostImportsResolver::function_type(import.name()).as_ref()
+                        == Some(ty)
+                }
+                _ => false,
+            };
+
+        if !known {
+            return Err(VMError::UnknownImport {
+                namespace: import.module().to_string(),
+                name: import.name().to_string(),
+            });
+        }
+    }
+
+    let mut has_memory = false;
+    let mut has_scratch = false;
+
+    for export in module.exports() {
+        trace!("Module exports {}", export.name());
+
+        match (export.name(), export.ty()) {
+            (MEMORY_NAME, ExternType::Memory(_)) => has_memory = true,
+            (SCRATCH_NAME, ExternType::Global(global)) => {
+                has_scratch = global.ty == Type::I32
+            }
+            _ => (),
+        }
+    }
+
+    if !has_memory {
+        return Err(VMError::MissingExport(MEMORY_NAME.to_string()));
+    }
+    if !has_scratch {
+        return Err(VMError::MissingExport(SCRATCH_NAME.to_string()));
+    }
+
+    Ok(())
+}
+
 #[derive(Clone, Hash, PartialEq, Eq)]
 struct ModuleCacheKey {
     hash: [u8; 32],
@@ -45,7 +137,8 @@ struct ModuleCacheKey {
 
 // The `cached` crate is used to generate a cache for calls to this function.
 // This is done to prevent modules from being compiled over and over again,
-// saving some CPU cycles.
+// saving some CPU cycles. The cache is behind a mutex, and compiled modules
+// are shared between the threads using it.
 cached_key_result! {
     COMPUTE: TimedSizedCache<ModuleCacheKey, Module>
         = TimedSizedCache::with_size_and_lifespan(2048, 86400);
@@ -55,21 +148,27 @@ cached_key_result! {
 
     fn get_or_create_module(bytecode: &[u8], config: &'static Config) -> Result<Module, VMError> = {
         trace!("Compiling module");
-        WasmerCompiler::create_module(bytecode, config)
+        let module = WasmerCompiler::create_module(bytecode, config)?;
+        validate_module(&module)?;
+        Ok(module)
     }
 }
 
-/// A cheaply cloneable store for host modules.
+/// A cheaply cloneable store for host modules, which can be shared between
+/// threads.
 #[derive(Clone, Default)]
-pub struct HostModules(Rc<RefCell<HashMap<ContractId, BoxedHostModule>>>);
+pub struct HostModules(Arc<RwLock<HashMap<ContractId, BoxedHostModule>>>);
 
-/// A `Ref` to a particular host module.
+/// A reference to a particular host module.
+///
+/// The store is locked for reading as long as the reference is held.
 pub struct HostModuleRef<'a> {
-    map_ref: Ref<'a, HashMap<ContractId, BoxedHostModule>>,
+    map_ref: RwLockReadGuard<'a, HashMap<ContractId, BoxedHostModule>>,
     id: &'a ContractId,
 }
 
 impl<'a> HostModuleRef<'a> {
+    /// Returns the module, if one is registered under the id.
     pub fn get(&self) -> Option<&BoxedHostModule> {
         self.map_ref.get(self.id)
     }
@@ -82,7 +181,8 @@ impl HostModules {
         M: 'static + HostModule,
     {
         self.0
-            .borrow_mut()
+            .write()
+            .expect("Host modules lock poisoned")
             .insert(module.module_id(), Box::new(module));
     }
 
@@ -92,7 +192,7 @@ impl HostModules {
         id: &'a ContractId,
     ) -> HostModuleRef<'a> {
         HostModuleRef {
-            map_ref: self.0.borrow(),
+            map_ref: self.0.read().expect("Host modules lock poisoned"),
             id,
         }
     }
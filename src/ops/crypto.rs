@@ -0,0 +1,265 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Cryptographic primitives that are too expensive to run in metered guest
+//! code.
+//!
+//! Each of them is charged a fixed cost per call, plus a cost per byte of
+//! input for those taking input of variable length. Invalid inputs - such as
+//! malformed keys or signatures - are not errors, and are reported to the
+//! contract through the return value.
+
+use dusk_bls12_381::BlsScalar;
+use dusk_bls12_381_sign::{
+    PublicKey as BlsPublicKey, Signature as BlsSignature,
+};
+use dusk_bytes::Serializable;
+use dusk_pki::PublicKey;
+use dusk_schnorr::Signature;
+use sha2::Digest;
+use tracing::trace;
+
+use crate::call_context::CallContext;
+use crate::env::Env;
+use crate::gas::Gas;
+use crate::state::hash::hash;
+use crate::VMError;
+
+/// Charges the cost of a call processing `len` bytes of input.
+fn charge(
+    context: &mut CallContext,
+    per_call: Gas,
+    per_byte: Gas,
+    len: usize,
+) -> Result<(), VMError> {
+    let cost = per_byte.saturating_mul(len as u64).saturating_add(per_call);
+    context.charge_gas(cost)
+}
+
+/// Reads `N` bytes from the memory of the calling contract.
+fn read_array<const N: usize>(
+    context: &CallContext,
+    ofs: i32,
+) -> Result<[u8; N], VMError> {
+    let mut array = [0u8; N];
+    array.copy_from_slice(context.read_memory(ofs as u64, N)?);
+    Ok(array)
+}
+
+pub struct Hash;
+
+impl Hash {
+    /// Write the blake2b-256 hash of the `len` bytes at `ofs` to `ret`.
+    pub fn hash(
+        env: &Env,
+        ofs: i32,
+        len: i32,
+        ret: i32,
+    ) -> Result<(), VMError> {
+        trace!("Executing 'hash' host function");
+
+        let len = len as u32 as usize;
+        let context = env.get_context();
+
+        let config = context.config();
+        let costs = &config.host_costs;
+        charge(context, costs.hash, costs.hash_per_byte, len)?;
+
+        let hash = hash(context.read_memory(ofs as u64, len)?);
+
+        context.write_memory(&hash, ret as u64)
+    }
+}
+
+pub struct Sha256;
+
+impl Sha256 {
+    /// Write the sha256 hash of the `len` bytes at `ofs` to `ret`.
+    pub fn sha256(
+        env: &Env,
+        ofs: i32,
+        len: u32,
+        ret: i32,
+    ) -> Result<(), VMError> {
+        trace!("Executing 'sha256' host function");
+
+        let context = env.get_context();
+
+        let config = context.config();
+        let costs = &config.host_costs;
+        charge(context, costs.sha256, costs.sha256_per_byte, len as usize)?;
+
+        let hash = sha2::Sha256::digest(
+            context.read_memory(ofs as u64, len as usize)?,
+        );
+
+        context.write_memory(&hash, ret as u64)
+    }
+}
+
+pub struct Keccak256;
+
+impl Keccak256 {
+    /// Write the keccak256 hash of the `len` bytes at `ofs` to `ret`.
+    pub fn keccak256(
+        env: &Env,
+        ofs: i32,
+        len: u32,
+        ret: i32,
+    ) -> Result<(), VMError> {
+        trace!("Executing 'keccak256' host function");
+
+        let context = env.get_context();
+
+        let config = context.config();
+        let costs = &config.host_costs;
+        charge(
+            context,
+            costs.keccak256,
+            costs.keccak256_per_byte,
+            len as usize,
+        )?;
+
+        let hash = sha3::Keccak256::digest(
+            context.read_memory(ofs as u64, len as usize)?,
+        );
+
+        context.write_memory(&hash, ret as u64)
+    }
+}
+
+pub struct PoseidonHash;
+
+impl PoseidonHash {
+    /// Write the poseidon hash of the scalars in the `len` bytes at `ofs` to
+    /// `ret`.
+    ///
+    /// Returns 1 on success, and 0 if the bytes are not a sequence of
+    /// canonically encoded scalars.
+    pub fn poseidon_hash(
+        env: &Env,
+        ofs: i32,
+        len: u32,
+        ret: i32,
+    ) -> Result<u32, VMError> {
+        trace!("Executing 'poseidon_hash' host function");
+
+        let context = env.get_context();
+
+        let config = context.config();
+        let costs = &config.host_costs;
+        charge(
+            context,
+            costs.poseidon_hash,
+            costs.poseidon_hash_per_byte,
+            len as usize,
+        )?;
+
+        let bytes = context.read_memory(ofs as u64, len as usize)?;
+        if bytes.len() % BlsScalar::SIZE != 0 {
+            return Ok(0);
+        }
+
+        let scalars: Option<Vec<BlsScalar>> = bytes
+            .chunks_exact(BlsScalar::SIZE)
+            .map(|chunk| {
+                let mut scalar = [0u8; BlsScalar::SIZE];
+                scalar.copy_from_slice(chunk);
+                BlsScalar::from_bytes(&scalar).ok()
+            })
+            .collect();
+
+        match scalars {
+            Some(scalars) => {
+                let hash = dusk_poseidon::sponge::hash(&scalars);
+                context.write_memory(&hash.to_bytes(), ret as u64)?;
+                Ok(1)
+            }
+            None => Ok(0),
+        }
+    }
+}
+
+pub struct SchnorrVerify;
+
+impl SchnorrVerify {
+    /// Verify the Schnorr signature at `sig` of the message scalar at `msg`
+    /// against the public key at `pk`.
+    ///
+    /// Returns 1 if the signature is valid, and 0 otherwise.
+    pub fn schnorr_verify(
+        env: &Env,
+        pk: i32,
+        msg: i32,
+        sig: i32,
+    ) -> Result<u32, VMError> {
+        trace!("Executing 'schnorr_verify' host function");
+
+        let context = env.get_context();
+
+        let config = context.config();
+        let costs = &config.host_costs;
+        context.charge_gas(costs.schnorr_verify)?;
+
+        let pk: [u8; PublicKey::SIZE] = read_array(context, pk)?;
+        let msg: [u8; BlsScalar::SIZE] = read_array(context, msg)?;
+        let sig: [u8; Signature::SIZE] = read_array(context, sig)?;
+
+        let valid = match (
+            PublicKey::from_bytes(&pk),
+            BlsScalar::from_bytes(&msg),
+            Signature::from_bytes(&sig),
+        ) {
+            (Ok(pk), Ok(msg), Ok(sig)) => sig.verify(&pk, msg),
+            _ => false,
+        };
+
+        Ok(valid as u32)
+    }
+}
+
+pub struct BlsVerify;
+
+impl BlsVerify {
+    /// Verify the BLS signature at `sig` of the `msg_len` bytes at `msg`
+    /// against the public key at `pk`.
+    ///
+    /// Returns 1 if the signature is valid, and 0 otherwise.
+    pub fn bls_verify(
+        env: &Env,
+        pk: i32,
+        sig: i32,
+        msg: i32,
+        msg_len: u32,
+    ) -> Result<u32, VMError> {
+        trace!("Executing 'bls_verify' host function");
+
+        let context = env.get_context();
+
+        let config = context.config();
+        let costs = &config.host_costs;
+        charge(
+            context,
+            costs.bls_verify,
+            costs.bls_verify_per_byte,
+            msg_len as usize,
+        )?;
+
+        let pk: [u8; BlsPublicKey::SIZE] = read_array(context, pk)?;
+        let sig: [u8; BlsSignature::SIZE] = read_array(context, sig)?;
+        let msg = context.read_memory(msg as u64, msg_len as usize)?;
+
+        let valid = match (
+            BlsPublicKey::from_bytes(&pk),
+            BlsSignature::from_bytes(&sig),
+        ) {
+            (Ok(pk), Ok(sig)) => pk.verify(&sig, msg).is_ok(),
+            _ => false,
+        };
+
+        Ok(valid as u32)
+    }
+}
@@ -0,0 +1,43 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use tracing::trace;
+
+use crate::env::Env;
+use crate::VMError;
+
+pub struct Deploy;
+
+impl Deploy {
+    /// Deploy a new contract with the given bytecode and initial state,
+    /// writing its id to `result_ofs`.
+    pub fn deploy(
+        env: &Env,
+        code_ofs: i32,
+        code_len: u32,
+        state_ofs: i32,
+        state_len: u32,
+        result_ofs: i32,
+    ) -> Result<(), VMError> {
+        trace!("Executing 'deploy' host function");
+
+        let context = env.get_context();
+
+        let config = context.config();
+        context.charge_gas(code_len as u64 * config.host_costs.deploy)?;
+
+        let code = context
+            .read_memory(code_ofs as u64, code_len as usize)?
+            .to_vec();
+        let state = context
+            .read_memory(state_ofs as u64, state_len as usize)?
+            .to_vec();
+
+        let id = context.deploy(code, state)?;
+
+        context.write_memory(id.as_bytes(), result_ofs as u64)
+    }
+}
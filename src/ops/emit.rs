@@ -4,46 +4,77 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use rkyv::AlignedVec;
 use tracing::trace;
 
 use crate::env::Env;
 use crate::VMError;
 
+/// Size of an indexed event topic.
+const TOPIC_SIZE: usize = 32;
+
 pub struct Emit;
 
 impl Emit {
-    /// Emit an event to the virtual machine. This event
+    /// Emit an event to the virtual machine, indexed by the topics in the
+    /// `topics_len` bytes at `topics_ofs`.
     pub fn emit(
         env: &Env,
         data_ofs: i32,
         data_len: u32,
         name_ofs: i32,
         name_len: u32,
+        topics_ofs: i32,
+        topics_len: u32,
     ) -> Result<(), VMError> {
         trace!("Executing 'emit' host function");
 
         let context = env.get_context();
 
-        let config = context.config();
-        context.charge_gas(config.host_costs.emit)?;
-
         let data_ofs = data_ofs as u64;
         let data_len = data_len as usize;
 
         let name_ofs = name_ofs as u64;
         let name_len = name_len as usize;
 
+        let topics_ofs = topics_ofs as u64;
+        let topics_len = topics_len as usize;
+
+        if topics_len % TOPIC_SIZE != 0 {
+            return Err(VMError::InvalidData);
+        }
+        let topic_num = topics_len / TOPIC_SIZE;
+
+        let config = context.config();
+        let costs = &config.host_costs;
+        context.charge_gas(
+            costs
+                .emit_per_topic
+                .saturating_mul(topic_num as u64)
+                .saturating_add(costs.emit),
+        )?;
+
         let origin = *context.callee();
 
         let data_memory = context.read_memory(data_ofs, data_len)?;
         let name_memory = context.read_memory(name_ofs, name_len)?;
+        let topics_memory = context.read_memory(topics_ofs, topics_len)?;
 
-        let data = data_memory.to_vec();
+        let mut data = AlignedVec::new();
+        data.extend_from_slice(data_memory);
         let name = String::from_utf8(name_memory.to_vec())
             .map_err(|_| VMError::InvalidUtf8)?;
+        let topics = topics_memory
+            .chunks_exact(TOPIC_SIZE)
+            .map(|chunk| {
+                let mut topic = [0u8; TOPIC_SIZE];
+                topic.copy_from_slice(chunk);
+                topic
+            })
+            .collect();
 
         // push an event to the event stack
-        context.push_event(origin, name, data);
+        context.push_event(origin, name, topics, data);
 
         Ok(())
     }
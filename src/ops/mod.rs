@@ -6,10 +6,16 @@
 
 pub mod block_height;
 pub mod call_stack;
+pub mod crypto;
 pub mod debug;
+pub mod deploy;
 pub mod emit;
 pub mod gas;
 pub mod panic;
+pub mod proof;
 pub mod query;
+pub mod self_destruct;
+pub mod storage;
 pub mod store;
 pub mod transact;
+pub mod upgrade;
@@ -0,0 +1,140 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::sync::Arc;
+
+use cached::cached_key_result;
+use cached::SizedCache;
+use dusk_bls12_381::BlsScalar;
+use dusk_bytes::Serializable;
+use dusk_plonk::circuit::{self, PublicInputValue, VerifierData};
+use dusk_plonk::proof_system::Proof;
+use rusk_uplink::PROOF_TRANSCRIPT_LABEL;
+use tracing::trace;
+
+use crate::env::Env;
+use crate::state::hash::hash;
+use crate::VMError;
+
+// Verifier data is usually the same for all the calls to a contract, so it
+// is parsed once and kept by hash, saving the cost of deserializing its
+// commitments on every call.
+cached_key_result! {
+    VERIFIER_DATA: SizedCache<[u8; 32], Arc<VerifierData>>
+        = SizedCache::with_size(256);
+    Key = { hash(bytes) };
+
+    fn parse_verifier_data(bytes: &[u8]) -> Result<Arc<VerifierData>, VMError> = {
+        trace!("Parsing verifier data");
+        let vd = VerifierData::from_slice(bytes)
+            .map_err(|_| VMError::InvalidData)?;
+
+        // Public inputs are placed in the gates of the circuit, and verifying
+        // against positions past its end panics
+        let padded_gates = vd.key().padded_gates();
+        if vd.pi_pos().iter().any(|pos| *pos >= padded_gates) {
+            return Err(VMError::InvalidData);
+        }
+
+        Ok(Arc::new(vd))
+    }
+}
+
+/// Parses public inputs encoded as a sequence of scalars.
+fn parse_public_inputs(bytes: &[u8]) -> Option<Vec<PublicInputValue>> {
+    if bytes.len() % BlsScalar::SIZE != 0 {
+        return None;
+    }
+
+    bytes
+        .chunks_exact(BlsScalar::SIZE)
+        .map(|chunk| {
+            let mut scalar = [0u8; BlsScalar::SIZE];
+            scalar.copy_from_slice(chunk);
+            BlsScalar::from_bytes(&scalar).ok().map(Into::into)
+        })
+        .collect()
+}
+
+pub struct VerifyProof;
+
+impl VerifyProof {
+    /// Verify a PLONK proof against the given verifier data and public
+    /// inputs, using the public parameters the state was built with.
+    ///
+    /// Returns 1 if the proof is valid, and 0 otherwise - including when any
+    /// of the inputs is malformed.
+    pub fn verify_proof(
+        env: &Env,
+        vd_ofs: i32,
+        vd_len: u32,
+        proof_ofs: i32,
+        proof_len: u32,
+        inputs_ofs: i32,
+        inputs_len: u32,
+    ) -> Result<u32, VMError> {
+        trace!("Executing 'verify_proof' host function");
+
+        let context = env.get_context();
+
+        // Charged before anything is read, since hashing the verifier data
+        // to look it up in the cache is already proportional to its length.
+        let config = context.config();
+        let costs = &config.host_costs;
+        let bytes = (vd_len as u64).saturating_add(proof_len as u64);
+        let inputs = inputs_len as u64 / BlsScalar::SIZE as u64;
+        context.charge_gas(
+            costs
+                .verify_proof_per_byte
+                .saturating_mul(bytes)
+                .saturating_add(
+                    costs.verify_proof_per_input.saturating_mul(inputs),
+                )
+                .saturating_add(costs.verify_proof),
+        )?;
+
+        let vd = context.read_memory(vd_ofs as u64, vd_len as usize)?;
+        let proof =
+            context.read_memory(proof_ofs as u64, proof_len as usize)?;
+        let inputs =
+            context.read_memory(inputs_ofs as u64, inputs_len as usize)?;
+
+        let pp = context
+            .public_parameters()
+            .ok_or(VMError::MissingPublicParameters)?;
+
+        let vd = match parse_verifier_data(vd) {
+            Ok(vd) => vd,
+            Err(_) => return Ok(0),
+        };
+        let proof = match Proof::from_slice(proof) {
+            Ok(proof) => proof,
+            Err(_) => return Ok(0),
+        };
+        let inputs = match parse_public_inputs(inputs) {
+            Some(inputs) => inputs,
+            None => return Ok(0),
+        };
+
+        // Each input is paired with a position, and extra or missing ones
+        // would otherwise be dropped or taken as zero
+        if inputs.len() != vd.pi_pos().len() {
+            return Ok(0);
+        }
+
+        let valid = circuit::verify_proof(
+            &pp,
+            vd.key(),
+            &proof,
+            &inputs,
+            vd.pi_pos(),
+            PROOF_TRANSCRIPT_LABEL,
+        )
+        .is_ok();
+
+        Ok(valid as u32)
+    }
+}
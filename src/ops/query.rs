@@ -9,7 +9,7 @@ use crate::VMError;
 
 use core::mem::size_of;
 use rkyv::AlignedVec;
-use rusk_uplink::{ContractId, RawQuery};
+use rusk_uplink::{ContractId, RawQuery, QUERY_BUFFER_FLAG, QUERY_ERROR_FLAG};
 use std::str;
 use tracing::trace;
 
@@ -21,6 +21,7 @@ impl ExecuteQuery {
         contract_id_ofs: i32,
         query_ofs: i32,
         query_len: u32,
+        query_cap: u32,
         name_ofs: i32,
         name_len: u32,
         gas_limit: u64,
@@ -35,6 +36,7 @@ impl ExecuteQuery {
         let contract_id_ofs = contract_id_ofs as u64;
         let query_ofs = query_ofs as u64;
         let query_len = query_len as usize;
+        let query_cap = query_cap as usize;
         let name_ofs = name_ofs as u64;
         let name_len = name_len as usize;
 
@@ -54,10 +56,35 @@ impl ExecuteQuery {
 
         let raw_query = RawQuery::from(query_data, name);
         let context = env.get_context();
-        let result = context.query(contract_id, raw_query, &mut gas_meter)?;
+        match context.query(contract_id, raw_query, &mut gas_meter) {
+            Ok(result) => {
+                let len = result.data_len();
+                if len > query_cap {
+                    if len >= QUERY_BUFFER_FLAG as usize {
+                        return Err(VMError::ArgumentTooLarge(len));
+                    }
 
-        context.write_memory(result.data(), query_ofs)?;
+                    // The caller retries the query with a buffer large enough
+                    // for its result
+                    return Ok(QUERY_BUFFER_FLAG | len as u32);
+                }
+                context.write_memory(result.data(), query_ofs)?;
 
-        Ok(result.data_len() as u32)
+                Ok(result.data_len() as u32)
+            }
+            Err(error) => {
+                trace!("query to {} failed: {}", contract_id, error);
+
+                // If the caller ran out of gas paying for the call it is
+                // aborted as well, otherwise it gets to handle the error
+                context.gas_meter()?;
+
+                let mut error = error.to_call_error().to_bytes();
+                error.truncate(query_cap);
+                context.write_memory(&error, query_ofs)?;
+
+                Ok(QUERY_ERROR_FLAG | error.len() as u32)
+            }
+        }
     }
 }
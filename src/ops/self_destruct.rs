@@ -0,0 +1,28 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use tracing::trace;
+
+use crate::env::Env;
+use crate::VMError;
+
+pub struct SelfDestruct;
+
+impl SelfDestruct {
+    /// Remove the calling contract from the state, journaling it so that it
+    /// is restored should the call fail.
+    pub fn self_destruct(env: &Env) -> Result<(), VMError> {
+        trace!("Executing 'self_destruct' host function");
+
+        let context = env.get_context();
+
+        let config = context.config();
+        context.charge_gas(config.host_costs.self_destruct)?;
+
+        let callee = *context.callee();
+        context.remove_contract(callee)
+    }
+}
@@ -0,0 +1,125 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use rusk_uplink::STORAGE_ABSENT;
+use tracing::trace;
+
+use crate::env::Env;
+use crate::VMError;
+
+pub struct StorageGet;
+
+impl StorageGet {
+    /// Write the value stored under the given key in the calling contract's
+    /// storage to `buf_ofs`, truncated to `buf_len` bytes.
+    ///
+    /// Returns the full length of the value, or [`STORAGE_ABSENT`] if there
+    /// is no value under the key.
+    pub fn storage_get(
+        env: &Env,
+        key_ofs: i32,
+        key_len: u32,
+        buf_ofs: i32,
+        buf_len: u32,
+    ) -> Result<u32, VMError> {
+        trace!("Executing 'storage_get' host function");
+
+        let context = env.get_context();
+
+        let config = context.config();
+        let costs = &config.host_costs;
+        context.charge_gas(
+            costs
+                .storage_get_per_byte
+                .saturating_mul(key_len as u64)
+                .saturating_add(costs.storage_get),
+        )?;
+
+        let key = context
+            .read_memory(key_ofs as u64, key_len as usize)?
+            .to_vec();
+
+        match context.storage_get(&key)? {
+            Some(value) => {
+                let len = value.len().min(buf_len as usize);
+                context.charge_gas(
+                    costs.storage_get_per_byte.saturating_mul(len as u64),
+                )?;
+                context.write_memory(&value[..len], buf_ofs as u64)?;
+
+                Ok(value.len() as u32)
+            }
+            None => Ok(STORAGE_ABSENT),
+        }
+    }
+}
+
+pub struct StorageSet;
+
+impl StorageSet {
+    /// Store a value under the given key in the calling contract's storage.
+    pub fn storage_set(
+        env: &Env,
+        key_ofs: i32,
+        key_len: u32,
+        value_ofs: i32,
+        value_len: u32,
+    ) -> Result<(), VMError> {
+        trace!("Executing 'storage_set' host function");
+
+        let context = env.get_context();
+
+        let config = context.config();
+        let costs = &config.host_costs;
+        let len = key_len as u64 + value_len as u64;
+        context.charge_gas(
+            costs
+                .storage_set_per_byte
+                .saturating_mul(len)
+                .saturating_add(costs.storage_set),
+        )?;
+
+        let key = context
+            .read_memory(key_ofs as u64, key_len as usize)?
+            .to_vec();
+        let value = context
+            .read_memory(value_ofs as u64, value_len as usize)?
+            .to_vec();
+
+        context.storage_set(&key, value)
+    }
+}
+
+pub struct StorageRemove;
+
+impl StorageRemove {
+    /// Remove the value stored under the given key in the calling contract's
+    /// storage, returning 1 if there was one and 0 otherwise.
+    pub fn storage_remove(
+        env: &Env,
+        key_ofs: i32,
+        key_len: u32,
+    ) -> Result<u32, VMError> {
+        trace!("Executing 'storage_remove' host function");
+
+        let context = env.get_context();
+
+        let config = context.config();
+        let costs = &config.host_costs;
+        context.charge_gas(
+            costs
+                .storage_remove_per_byte
+                .saturating_mul(key_len as u64)
+                .saturating_add(costs.storage_remove),
+        )?;
+
+        let key = context
+            .read_memory(key_ofs as u64, key_len as usize)?
+            .to_vec();
+
+        context.storage_remove(&key).map(u32::from)
+    }
+}
@@ -8,12 +8,14 @@ use microkelvin::OffsetLen;
 use tracing::trace;
 
 use crate::env::Env;
+use crate::state::get_raw_checked;
 use crate::VMError;
 
 pub struct Get;
 
 impl Get {
-    /// Write bytes into wasm memory
+    /// Write the `len` bytes stored at `ofs` into wasm memory, failing if
+    /// they lie past the end of the store.
     pub fn get(
         env: &Env,
         ofs: u64,
@@ -29,7 +31,12 @@ impl Get {
         let id = OffsetLen::new(ofs, len);
 
         let store = env.store();
-        let slice = store.get_raw(&id);
+        let slice = get_raw_checked(store, &id).ok_or(
+            VMError::MemoryAccessOutOfBounds {
+                offset: ofs,
+                len: len as usize,
+            },
+        )?;
 
         context.write_memory(slice, buf_ptr as u64)?;
 
@@ -45,7 +52,9 @@ impl Put {
         let context = env.get_context();
 
         let config = context.config();
-        context.charge_gas(len as u64 * config.host_costs.put)?;
+        let len = len as u32;
+        context
+            .charge_gas((len as u64).saturating_mul(config.host_costs.put))?;
 
         let bytes = env
             .get_context()
@@ -54,27 +63,3 @@ impl Put {
         Ok(i.offset())
     }
 }
-
-pub struct Hash;
-
-impl Hash {
-    pub fn hash(
-        env: &Env,
-        ofs: i32,
-        len: i32,
-        ret: i32,
-    ) -> Result<(), VMError> {
-        let ofs = ofs as u64;
-        let len = len as usize;
-        let ret = ret as u64;
-        let context = env.get_context();
-
-        let config = context.config();
-        context.charge_gas(config.host_costs.hash)?;
-
-        let mem = context.read_memory(ofs, len)?;
-        let hash = mem.to_vec();
-
-        context.write_memory(&hash, ret)
-    }
-}
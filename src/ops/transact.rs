@@ -7,7 +7,9 @@
 use core::mem::size_of;
 use rkyv::AlignedVec;
 
-use rusk_uplink::{ContractId, RawTransaction};
+use rusk_uplink::{
+    ContractId, RawTransaction, TRANSACT_BUFFER_FLAG, TRANSACT_ERROR_FLAG,
+};
 use std::str;
 use tracing::trace;
 
@@ -22,6 +24,7 @@ impl ApplyTransaction {
         contract_id_ofs: i32,
         transact_ofs: i32,
         transact_len: u32,
+        transact_cap: u32,
         name_ofs: i32,
         name_len: u32,
         gas_limit: u64,
@@ -36,6 +39,7 @@ impl ApplyTransaction {
         let contract_id_ofs = contract_id_ofs as u64;
         let transact_ofs = transact_ofs as u64;
         let transact_len = transact_len as usize;
+        let transact_cap = transact_cap as usize;
         let name_ofs = name_ofs as u64;
         let name_len = name_len as usize;
 
@@ -55,15 +59,37 @@ impl ApplyTransaction {
 
         let raw_transaction = RawTransaction::from(query_data, name);
         let context = env.get_context();
-        let result =
-            context.transact(contract_id, raw_transaction, &mut gas_meter)?;
+        let checkpoint = context.checkpoint();
+        match context.transact(contract_id, raw_transaction, &mut gas_meter) {
+            Ok(result) => {
+                let len = result.state_len() + result.data_len();
+                if len > transact_cap {
+                    // The caller retries the transaction with a buffer large
+                    // enough for its result, so it is undone until then
+                    context.rollback(checkpoint)?;
+                    return Ok(TRANSACT_BUFFER_FLAG | len as u64);
+                }
+                context.write_memory(result.state(), transact_ofs)?;
+                context.write_memory(
+                    result.data(),
+                    transact_ofs + result.state_len() as u64,
+                )?;
 
-        context.write_memory(result.state(), transact_ofs)?;
-        context.write_memory(
-            result.data(),
-            transact_ofs + result.state_len() as u64,
-        )?;
+                Ok(result.encode_lenghts())
+            }
+            Err(error) => {
+                trace!("transaction to {} failed: {}", contract_id, error);
 
-        Ok(result.encode_lenghts())
+                // If the caller ran out of gas paying for the call it is
+                // aborted as well, otherwise it gets to handle the error
+                context.gas_meter()?;
+
+                let mut error = error.to_call_error().to_bytes();
+                error.truncate(transact_cap);
+                context.write_memory(&error, transact_ofs)?;
+
+                Ok(TRANSACT_ERROR_FLAG | error.len() as u64)
+            }
+        }
     }
 }
@@ -0,0 +1,37 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use tracing::trace;
+
+use crate::env::Env;
+use crate::VMError;
+
+pub struct Upgrade;
+
+impl Upgrade {
+    /// Replace the bytecode of the calling contract with the given one,
+    /// keeping its state, storage and nonce.
+    pub fn upgrade(
+        env: &Env,
+        code_ofs: i32,
+        code_len: u32,
+    ) -> Result<(), VMError> {
+        trace!("Executing 'upgrade' host function");
+
+        let context = env.get_context();
+
+        let config = context.config();
+        context.charge_gas(
+            config.host_costs.upgrade.saturating_mul(code_len as u64),
+        )?;
+
+        let code = context
+            .read_memory(code_ofs as u64, code_len as usize)?
+            .to_vec();
+
+        context.upgrade(code)
+    }
+}
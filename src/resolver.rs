@@ -7,11 +7,47 @@
 use crate::env::Env;
 use crate::ops::*;
 
-use wasmer::{Exports, Function, Store};
+use wasmer::{Exports, Function, FunctionType, Store, Type};
+
+/// Namespaces the host functions are imported from.
+pub const NAMESPACES: [&str; 2] = ["env", "canon"];
 
 pub struct HostImportsResolver;
 
 impl HostImportsResolver {
+    /// Returns the type of the host function with the given name, or `None`
+    /// if there is no such function.
+    ///
+    /// Must be kept in sync with the functions inserted by
+    /// [`insert_into_namespace`](HostImportsResolver::insert_into_namespace).
+    pub fn function_type(name: &str) -> Option<FunctionType> {
+        use Type::{I32, I64};
+
+        let (params, results): (&[Type], &[Type]) = match name {
+            "sig" | "debug" => (&[I32, I32], &[]),
+            "block_height" | "gas_consumed" | "gas_left" => (&[], &[I64]),
+            "transact" => (&[I32, I32, I32, I32, I32, I32, I64], &[I64]),
+            "query" => (&[I32, I32, I32, I32, I32, I32, I64], &[I32]),
+            "deploy" => (&[I32, I32, I32, I32, I32], &[]),
+            "emit" => (&[I32, I32, I32, I32, I32, I32], &[]),
+            "storage_set" => (&[I32, I32, I32, I32], &[]),
+            "self_destruct" => (&[], &[]),
+            "upgrade" => (&[I32, I32], &[]),
+            "storage_get" => (&[I32, I32, I32, I32], &[I32]),
+            "storage_remove" => (&[I32, I32], &[I32]),
+            "callee" | "caller" => (&[I32], &[]),
+            "_get" => (&[I64, I32, I32], &[]),
+            "_put" => (&[I32, I32], &[I64]),
+            "hash" | "sha256" | "keccak256" => (&[I32, I32, I32], &[]),
+            "poseidon_hash" | "schnorr_verify" => (&[I32, I32, I32], &[I32]),
+            "bls_verify" => (&[I32, I32, I32, I32], &[I32]),
+            "verify_proof" => (&[I32, I32, I32, I32, I32, I32], &[I32]),
+            _ => return None,
+        };
+
+        Some(FunctionType::new(params, results))
+    }
+
     pub fn insert_into_namespace(
         namespace: &mut Exports,
         store: &Store,
@@ -60,6 +96,14 @@ impl HostImportsResolver {
                         query::ExecuteQuery::query,
                     ),
                 ),
+                "deploy" => namespace.insert(
+                    "deploy",
+                    Function::new_native_with_env(
+                        store,
+                        env.clone(),
+                        deploy::Deploy::deploy,
+                    ),
+                ),
                 "emit" => namespace.insert(
                     "emit",
                     Function::new_native_with_env(
@@ -68,6 +112,46 @@ impl HostImportsResolver {
                         emit::Emit::emit,
                     ),
                 ),
+                "self_destruct" => namespace.insert(
+                    "self_destruct",
+                    Function::new_native_with_env(
+                        store,
+                        env.clone(),
+                        self_destruct::SelfDestruct::self_destruct,
+                    ),
+                ),
+                "upgrade" => namespace.insert(
+                    "upgrade",
+                    Function::new_native_with_env(
+                        store,
+                        env.clone(),
+                        upgrade::Upgrade::upgrade,
+                    ),
+                ),
+                "storage_get" => namespace.insert(
+                    "storage_get",
+                    Function::new_native_with_env(
+                        store,
+                        env.clone(),
+                        storage::StorageGet::storage_get,
+                    ),
+                ),
+                "storage_set" => namespace.insert(
+                    "storage_set",
+                    Function::new_native_with_env(
+                        store,
+                        env.clone(),
+                        storage::StorageSet::storage_set,
+                    ),
+                ),
+                "storage_remove" => namespace.insert(
+                    "storage_remove",
+                    Function::new_native_with_env(
+                        store,
+                        env.clone(),
+                        storage::StorageRemove::storage_remove,
+                    ),
+                ),
                 "callee" => namespace.insert(
                     "callee",
                     Function::new_native_with_env(
@@ -105,7 +189,55 @@ impl HostImportsResolver {
                     Function::new_native_with_env(
                         store,
                         env.clone(),
-                        store::Hash::hash,
+                        crypto::Hash::hash,
+                    ),
+                ),
+                "sha256" => namespace.insert(
+                    "sha256",
+                    Function::new_native_with_env(
+                        store,
+                        env.clone(),
+                        crypto::Sha256::sha256,
+                    ),
+                ),
+                "keccak256" => namespace.insert(
+                    "keccak256",
+                    Function::new_native_with_env(
+                        store,
+                        env.clone(),
+                        crypto::Keccak256::keccak256,
+                    ),
+                ),
+                "poseidon_hash" => namespace.insert(
+                    "poseidon_hash",
+                    Function::new_native_with_env(
+                        store,
+                        env.clone(),
+                        crypto::PoseidonHash::poseidon_hash,
+                    ),
+                ),
+                "schnorr_verify" => namespace.insert(
+                    "schnorr_verify",
+                    Function::new_native_with_env(
+                        store,
+                        env.clone(),
+                        crypto::SchnorrVerify::schnorr_verify,
+                    ),
+                ),
+                "bls_verify" => namespace.insert(
+                    "bls_verify",
+                    Function::new_native_with_env(
+                        store,
+                        env.clone(),
+                        crypto::BlsVerify::bls_verify,
+                    ),
+                ),
+                "verify_proof" => namespace.insert(
+                    "verify_proof",
+                    Function::new_native_with_env(
+                        store,
+                        env.clone(),
+                        proof::VerifyProof::verify_proof,
                     ),
                 ),
                 "gas_consumed" => namespace.insert(
@@ -124,6 +256,8 @@ impl HostImportsResolver {
                         gas::GasLeft::gas_left,
                     ),
                 ),
+                // Modules importing unknown functions are rejected when
+                // compiled
                 _ => {
                     debug_assert!(false, "unknown wasm module import {}", name)
                 }
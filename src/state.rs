@@ -4,23 +4,34 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+pub mod abi;
+pub mod bloom;
 pub mod builder;
+mod compact;
 pub mod contracts;
+pub mod diff;
 pub mod hash;
+pub mod iter;
+mod migrate;
 pub mod persist;
 
 use std::fmt;
 use std::ops::Deref;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use bytecheck::CheckBytes;
+use dusk_plonk::commitment_scheme::PublicParameters;
 use microkelvin::{
-    BranchRef, BranchRefMut, OffsetLen, StoreRef, StoreSerializer,
+    BranchRef, BranchRefMut, MaybeArchived, OffsetLen, StoreRef,
+    StoreSerializer,
 };
-use rkyv::validation::validators::DefaultValidator;
-use rkyv::{Archive, Deserialize, Serialize};
+use rkyv::validation::validators::{DefaultValidator, DefaultValidatorError};
+use rkyv::validation::CheckArchiveError;
+use rkyv::{check_archived_root, AlignedVec, Archive, Deserialize, Serialize};
 use rusk_uplink::{
-    ContractId, Query, RawQuery, RawTransaction, StoreContext, Transaction,
+    ContractId, Query, RawQuery, RawTransaction, StateProof, StoreContext,
+    Transaction,
 };
 
 use tracing::{trace, trace_span};
@@ -32,19 +43,38 @@ use crate::error::VMError;
 use crate::gas::GasMeter;
 use crate::modules::HostModules;
 
+use bloom::Bloom;
 use builder::NetworkStateBuilder;
 use contracts::Contracts;
 
+/// An event emitted by a contract during a call.
 #[derive(Debug, Clone)]
 pub struct Event {
     origin: ContractId,
     name: String,
-    data: Vec<u8>,
+    topics: Vec<[u8; 32]>,
+    data: AlignedVec,
+    depth: usize,
+    frame: usize,
 }
 
 impl Event {
-    pub(crate) fn new(origin: ContractId, name: String, data: Vec<u8>) -> Self {
-        Self { origin, name, data }
+    pub(crate) fn new(
+        origin: ContractId,
+        name: String,
+        topics: Vec<[u8; 32]>,
+        data: AlignedVec,
+        depth: usize,
+        frame: usize,
+    ) -> Self {
+        Self {
+            origin,
+            name,
+            topics,
+            data,
+            depth,
+            frame,
+        }
     }
 
     /// The Id of the smart contract originating the event.
@@ -57,21 +87,61 @@ impl Event {
         self.name.as_str()
     }
 
+    /// The topics the event is indexed by.
+    pub fn topics(&self) -> &[[u8; 32]] {
+        &self.topics
+    }
+
     /// The data included with the event.
     pub fn data(&self) -> &[u8] {
         self.data.as_slice()
     }
+
+    /// Casts the data included with the event to the archived form of `T`,
+    /// validating it first.
+    pub fn cast<'a, T>(
+        &'a self,
+    ) -> Result<
+        &'a T::Archived,
+        CheckArchiveError<
+            <T::Archived as CheckBytes<DefaultValidator<'a>>>::Error,
+            DefaultValidatorError,
+        >,
+    >
+    where
+        T: Archive,
+        T::Archived: CheckBytes<DefaultValidator<'a>>,
+    {
+        check_archived_root::<T>(self.data.as_slice())
+    }
+
+    /// The depth of the call stack when the event was emitted, starting at
+    /// zero for the contract called directly.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The position of the emitting call among all the calls made during
+    /// execution, in the order they were made.
+    ///
+    /// Events emitted by the same call share the same frame.
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
 }
 
+/// The result of a call, along with the events emitted while executing it.
 #[derive(Debug, Clone)]
 pub struct Receipt<R> {
     ret: R,
     events: Vec<Event>,
+    bloom: Bloom,
 }
 
 impl<R> Receipt<R> {
     pub(crate) fn new(ret: R, events: Vec<Event>) -> Self {
-        Self { ret, events }
+        let bloom = events.iter().collect();
+        Self { ret, events, bloom }
     }
 
     /// The return of the smart contract call.
@@ -84,6 +154,26 @@ impl<R> Receipt<R> {
     pub fn events(&self) -> &[Event] {
         &self.events
     }
+
+    /// The events emitted by `origin` with the given `name`, in order of
+    /// emission.
+    pub fn events_by<'a>(
+        &'a self,
+        origin: &'a ContractId,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a Event> {
+        let bloom_hit = self.bloom.contains_event(origin, name);
+
+        self.events.iter().filter(move |event| {
+            bloom_hit && event.origin == *origin && event.name == name
+        })
+    }
+
+    /// Bloom filter over the origins, names and topics of the emitted
+    /// events.
+    pub fn bloom(&self) -> &Bloom {
+        &self.bloom
+    }
 }
 
 impl<R> Deref for Receipt<R> {
@@ -106,7 +196,8 @@ pub struct NetworkState {
     contracts: Contracts,
     modules: HostModules,
     store: StoreContext,
-    id_path: Option<PathBuf>,
+    store_dir: Option<PathBuf>,
+    public_parameters: Option<Arc<PublicParameters>>,
     config: &'static Config,
 }
 
@@ -126,6 +217,11 @@ impl NetworkState {
         self.config
     }
 
+    /// Returns the public parameters used to verify proofs, if any were set.
+    pub fn public_parameters(&self) -> Option<&Arc<PublicParameters>> {
+        self.public_parameters.as_ref()
+    }
+
     /// Returns the store backing the state.
     pub fn store(&self) -> &StoreContext {
         &self.store
@@ -162,7 +258,23 @@ impl NetworkState {
         self.contracts.deploy(contract, self.config)
     }
 
+    /// Deploys a contract to the state, returning the address of the
+    /// created contract or an error.
+    ///
+    /// The `salt` is included in the derivation of the address, allowing for
+    /// the same contract to be deployed more than once.
+    pub fn deploy_with_salt(
+        &mut self,
+        contract: Contract,
+        salt: &[u8],
+    ) -> Result<ContractId, VMError> {
+        self.contracts.deploy_with_salt(contract, salt, self.config)
+    }
+
     /// Deploys a contract to the state with the given id / address.
+    ///
+    /// Fails with [`VMError::ContractAlreadyExists`] if the id is already
+    /// taken.
     pub fn deploy_with_id(
         &mut self,
         id: ContractId,
@@ -171,6 +283,31 @@ impl NetworkState {
         self.contracts.deploy_with_id(id, contract, self.config)
     }
 
+    /// Replaces the contract at the given id / address with a new one,
+    /// returning the previous contract.
+    ///
+    /// Unlike [`NetworkState::deploy_with_id`], this is meant for intentional
+    /// upgrades and requires the contract to already exist. The new contract
+    /// takes over the nonce of the previous one.
+    pub fn replace_contract(
+        &mut self,
+        id: ContractId,
+        contract: Contract,
+    ) -> Result<Contract, VMError> {
+        self.contracts.replace_contract(id, contract, self.config)
+    }
+
+    /// Removes a contract from the state, returning it.
+    ///
+    /// Any further calls to the contract will fail with
+    /// [`VMError::UnknownContract`].
+    pub fn remove_contract(
+        &mut self,
+        contract_id: &ContractId,
+    ) -> Result<Contract, VMError> {
+        self.contracts.remove_contract(contract_id)
+    }
+
     /// Query the contract at `target` address in the state, returning the query
     /// receipt.
     pub fn query<Q>(
@@ -287,19 +424,54 @@ impl NetworkState {
         self.contracts.root()
     }
 
-    /// Gets the state of the given contract.
+    /// Returns a proof that the state of the given contract is part of the
+    /// [`root`].
+    ///
+    /// The proof can be checked with [`StateProof::verify`], also from within
+    /// a contract.
+    ///
+    /// [`root`]: NetworkState::root
+    pub fn prove_contract(
+        &self,
+        contract_id: &ContractId,
+    ) -> Result<StateProof, VMError> {
+        self.contracts.prove_contract(contract_id)
+    }
+
+    /// Gets the state of the given contract, validated and deserialized into
+    /// `C`.
+    ///
+    /// Returns [`VMError::InvalidData`] if the stored state bytes are not a
+    /// valid archive of `C`.
     pub fn get_contract_cast_state<C>(
         &self,
         contract_id: &ContractId,
-    ) -> Result<C, VMError> {
-        self.contracts.get_contract(contract_id).map_or(
-            Err(VMError::UnknownContract(*contract_id)),
-            |_contract| {
-                // let mut source = Source::new((*contract).state().as_bytes());
-                // C::decode(&mut source).map_err(VMError::from_store_error)
-                todo!()
-            },
-        )
+    ) -> Result<C, VMError>
+    where
+        C: Archive,
+        C::Archived: for<'a> CheckBytes<DefaultValidator<'a>>
+            + Deserialize<C, StoreRef<OffsetLen>>,
+    {
+        let contract = self.contracts.get_contract(contract_id)?;
+
+        // Copy the state into an aligned buffer, since the stored bytes carry
+        // no alignment guarantees
+        let mut state: AlignedVec = AlignedVec::new();
+        match contract.leaf() {
+            MaybeArchived::Memory(m) => state.extend_from_slice(m.state()),
+            MaybeArchived::Archived(a) => {
+                state.extend_from_slice(a.state(&self.store))
+            }
+        }
+
+        let cast = check_archived_root::<C>(state.as_slice())
+            .map_err(|_| VMError::InvalidData)?;
+
+        let state: C = cast
+            .deserialize(&mut self.store.clone())
+            .expect("Infallible");
+
+        Ok(state)
     }
 }
 
@@ -316,3 +488,19 @@ impl fmt::Debug for NetworkState {
             .finish_non_exhaustive()
     }
 }
+
+/// Returns the bytes stored at `id`, or `None` if they lie past the end of
+/// the store.
+///
+/// The store is only ever appended to, so the offset an empty write lands at
+/// marks its end.
+pub(crate) fn get_raw_checked<'a>(
+    store: &'a StoreContext,
+    id: &OffsetLen,
+) -> Option<&'a [u8]> {
+    let end = store.put_raw(&[]).offset();
+    match id.offset().checked_add(id.len() as u64) {
+        Some(id_end) if id_end <= end => Some(store.get_raw(id)),
+        _ => None,
+    }
+}
@@ -0,0 +1,231 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::fmt;
+use std::str;
+
+use microkelvin::{BranchRef, MaybeArchived};
+use rusk_uplink::ContractId;
+use wasmer::{ExternType, FunctionType, Module, Type};
+
+use crate::error::VMError;
+use crate::modules::compile_module;
+use crate::state::NetworkState;
+
+/// Name of the custom section the `#[execute]` and `#[apply]` macros emit
+/// to describe the entry points of a contract.
+///
+/// The section is a sequence of records of the form
+/// `<kind>\t<name>\t<argument type>\t<return type>\n`, where the kind is
+/// either `query` or `transaction`.
+pub const ABI_SECTION_NAME: &str = "rusk_abi";
+
+/// The kind of an entry point of a contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// A query, exported as `(i32, i32) -> i32`
+    Query,
+    /// A transaction, exported as `(i32, i32) -> i64`
+    Transaction,
+}
+
+impl EntryKind {
+    fn from_signature(ty: &FunctionType) -> Option<Self> {
+        match (ty.params(), ty.results()) {
+            ([Type::I32, Type::I32], [Type::I32]) => Some(EntryKind::Query),
+            ([Type::I32, Type::I32], [Type::I64]) => {
+                Some(EntryKind::Transaction)
+            }
+            _ => None,
+        }
+    }
+
+    fn from_record(kind: &str) -> Option<Self> {
+        match kind {
+            "query" => Some(EntryKind::Query),
+            "transaction" => Some(EntryKind::Transaction),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntryKind::Query => write!(f, "query"),
+            EntryKind::Transaction => write!(f, "transaction"),
+        }
+    }
+}
+
+/// Checks that the module of the contract at `contract` exports an entry
+/// point with the given `name` and `kind`, without instantiating it.
+pub(crate) fn check_entry(
+    module: &Module,
+    contract: ContractId,
+    name: &str,
+    kind: EntryKind,
+) -> Result<(), VMError> {
+    let found = module
+        .exports()
+        .find(|export| export.name() == name)
+        .and_then(|export| match export.ty() {
+            ExternType::Function(ty) => EntryKind::from_signature(ty),
+            _ => None,
+        });
+
+    match found {
+        Some(found) if found == kind => Ok(()),
+        Some(_) => Err(VMError::MethodKindMismatch {
+            contract,
+            name: name.to_string(),
+            expected: kind,
+        }),
+        None => Err(VMError::UnknownMethod {
+            contract,
+            name: name.to_string(),
+        }),
+    }
+}
+
+/// An entry point exported by a contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbiEntry {
+    name: String,
+    kind: EntryKind,
+    arg_type: Option<String>,
+    return_type: Option<String>,
+}
+
+impl AbiEntry {
+    /// The name the entry point is exported under.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the entry point is a query or a transaction.
+    pub fn kind(&self) -> EntryKind {
+        self.kind
+    }
+
+    /// The name of the argument type, if the contract describes it.
+    pub fn arg_type(&self) -> Option<&str> {
+        self.arg_type.as_deref()
+    }
+
+    /// The name of the return type, if the contract describes it.
+    pub fn return_type(&self) -> Option<&str> {
+        self.return_type.as_deref()
+    }
+}
+
+/// The entry points of a contract.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContractAbi {
+    entries: Vec<AbiEntry>,
+}
+
+impl ContractAbi {
+    /// Extracts the entry points of a compiled module.
+    ///
+    /// Exported functions are classified by their signature, and other
+    /// exports are ignored. The types are taken from the
+    /// [`ABI_SECTION_NAME`] section, when present.
+    pub(crate) fn from_module(module: &Module) -> Self {
+        let mut entries: Vec<AbiEntry> = module
+            .exports()
+            .filter_map(|export| match export.ty() {
+                ExternType::Function(ty) => {
+                    EntryKind::from_signature(ty).map(|kind| AbiEntry {
+                        name: export.name().to_string(),
+                        kind,
+                        arg_type: None,
+                        return_type: None,
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+
+        for section in module.custom_sections(ABI_SECTION_NAME) {
+            let section = match str::from_utf8(&section) {
+                Ok(section) => section,
+                Err(_) => continue,
+            };
+
+            for record in section.lines() {
+                let mut fields = record.split('\t');
+                let (kind, name, arg_type, return_type) = match (
+                    fields.next().and_then(EntryKind::from_record),
+                    fields.next(),
+                    fields.next(),
+                    fields.next(),
+                ) {
+                    (Some(kind), Some(name), Some(arg), Some(ret)) => {
+                        (kind, name, arg, ret)
+                    }
+                    _ => continue,
+                };
+
+                // Records are only trusted for the entry points that are
+                // actually exported with the matching signature
+                if let Some(entry) = entries
+                    .iter_mut()
+                    .find(|e| e.name == name && e.kind == kind)
+                {
+                    entry.arg_type = Some(arg_type.to_string());
+                    entry.return_type = Some(return_type.to_string());
+                }
+            }
+        }
+
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Self { entries }
+    }
+
+    /// All the entry points, sorted by name.
+    pub fn entries(&self) -> &[AbiEntry] {
+        &self.entries
+    }
+
+    /// Returns the entry point with the given name, if any.
+    pub fn get(&self, name: &str) -> Option<&AbiEntry> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    /// The queries of the contract.
+    pub fn queries(&self) -> impl Iterator<Item = &AbiEntry> {
+        self.entries.iter().filter(|e| e.kind == EntryKind::Query)
+    }
+
+    /// The transactions of the contract.
+    pub fn transactions(&self) -> impl Iterator<Item = &AbiEntry> {
+        self.entries
+            .iter()
+            .filter(|e| e.kind == EntryKind::Transaction)
+    }
+}
+
+impl NetworkState {
+    /// Returns the entry points of the given contract.
+    ///
+    /// The contract's module is compiled - or taken from the module cache -
+    /// but not instantiated.
+    pub fn contract_abi(
+        &self,
+        contract_id: &ContractId,
+    ) -> Result<ContractAbi, VMError> {
+        let contract = self.contracts.get_contract(contract_id)?;
+
+        let bytecode = match contract.leaf() {
+            MaybeArchived::Memory(m) => m.bytecode(),
+            MaybeArchived::Archived(a) => a.bytecode(&self.store),
+        };
+
+        let module = compile_module(bytecode, self.config)?;
+        Ok(ContractAbi::from_module(&module))
+    }
+}
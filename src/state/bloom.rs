@@ -0,0 +1,151 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Bloom filters over emitted events.
+//!
+//! Every [`Receipt`] carries a bloom of the origins, names and topics of its
+//! events, so that receipts - or whole blocks, by [`union`]-ing the blooms of
+//! their receipts - can be skipped when looking for a given event without
+//! going through the events themselves.
+//!
+//! [`Receipt`]: crate::Receipt
+//! [`union`]: Bloom::union
+
+use std::fmt;
+
+use rusk_uplink::ContractId;
+
+use super::hash::hash;
+use super::Event;
+
+/// Size of the bloom in bytes.
+pub const BLOOM_SIZE: usize = 256;
+
+const BLOOM_BITS: usize = BLOOM_SIZE * 8;
+
+/// Number of bits set per inserted item.
+const BLOOM_HASHES: usize = 3;
+
+/// A 2048 bit bloom filter, setting three bits per item.
+///
+/// A bloom may report items that were never inserted, but never misses one
+/// that was.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Bloom([u8; BLOOM_SIZE]);
+
+impl Bloom {
+    /// Creates an empty bloom.
+    pub const fn new() -> Self {
+        Self([0; BLOOM_SIZE])
+    }
+
+    /// Creates a bloom from its byte representation.
+    pub const fn from_bytes(bytes: [u8; BLOOM_SIZE]) -> Self {
+        Self(bytes)
+    }
+
+    /// The byte representation of the bloom.
+    pub fn as_bytes(&self) -> &[u8; BLOOM_SIZE] {
+        &self.0
+    }
+
+    /// Returns true if no item was inserted in the bloom.
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|byte| *byte == 0)
+    }
+
+    /// Inserts an item in the bloom.
+    pub fn insert<B: AsRef<[u8]>>(&mut self, item: B) {
+        for bit in bits(item.as_ref()) {
+            self.0[bit / 8] |= 1 << (bit % 8);
+        }
+    }
+
+    /// Returns true if the item may have been inserted in the bloom, and
+    /// false if it was definitely not.
+    pub fn contains<B: AsRef<[u8]>>(&self, item: B) -> bool {
+        bits(item.as_ref()).all(|bit| self.0[bit / 8] & (1 << (bit % 8)) != 0)
+    }
+
+    /// Inserts the origin, name and topics of an event in the bloom.
+    pub fn insert_event(&mut self, event: &Event) {
+        let origin = event.origin();
+        self.insert(origin.as_bytes());
+        self.insert(event_key(&origin, event.name()));
+        for topic in event.topics() {
+            self.insert(topic);
+        }
+    }
+
+    /// Returns true if an event from the given origin may have been inserted
+    /// in the bloom.
+    pub fn contains_origin(&self, origin: &ContractId) -> bool {
+        self.contains(origin.as_bytes())
+    }
+
+    /// Returns true if an event with the given origin and name may have been
+    /// inserted in the bloom.
+    pub fn contains_event(&self, origin: &ContractId, name: &str) -> bool {
+        self.contains(event_key(origin, name))
+    }
+
+    /// Returns true if an event indexed by the given topic may have been
+    /// inserted in the bloom.
+    pub fn contains_topic(&self, topic: &[u8; 32]) -> bool {
+        self.contains(topic)
+    }
+
+    /// Adds all the items inserted in `other` to the bloom.
+    pub fn union(&mut self, other: &Bloom) {
+        for (byte, other) in self.0.iter_mut().zip(other.0.iter()) {
+            *byte |= other;
+        }
+    }
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Bloom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Bloom(")?;
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl<'a> FromIterator<&'a Event> for Bloom {
+    fn from_iter<I: IntoIterator<Item = &'a Event>>(events: I) -> Self {
+        let mut bloom = Bloom::new();
+        for event in events {
+            bloom.insert_event(event);
+        }
+        bloom
+    }
+}
+
+/// Names are inserted along with their origin, since the same name may be used
+/// by unrelated contracts.
+fn event_key(origin: &ContractId, name: &str) -> Vec<u8> {
+    let mut key = Vec::with_capacity(32 + name.len());
+    key.extend_from_slice(origin.as_bytes());
+    key.extend_from_slice(name.as_bytes());
+    key
+}
+
+/// The bits an item sets in the bloom, taken from pairs of bytes of its hash.
+fn bits(item: &[u8]) -> impl Iterator<Item = usize> {
+    let hash = hash(item);
+    (0..BLOOM_HASHES).map(move |i| {
+        let pair = [hash[2 * i], hash[2 * i + 1]];
+        u16::from_le_bytes(pair) as usize % BLOOM_BITS
+    })
+}
@@ -5,31 +5,34 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use crate::config::{Config, DEFAULT_CONFIG};
-use crate::contract::Contract;
 use crate::modules::{HostModule, HostModules};
-use crate::state::contracts::HashAnnotation;
-use crate::state::{Contracts, NetworkState};
+use crate::state::compact::open_store;
+use crate::state::contracts::{ContractsTree, HASH_VERSION};
+use crate::state::migrate::migrate_v0;
+use crate::state::persist::{
+    persist_migrated, read_log, read_persist_id, PersistEntry, PersistError,
+};
+use crate::state::{get_raw_checked, Contracts, NetworkState};
 
-use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use dusk_hamt::Hamt;
-use microkelvin::{HostStore, Ident, OffsetLen};
-use rkyv::{archived_root, Archive, Deserialize, Infallible};
-use rusk_uplink::{ContractId, StoreContext};
+use dusk_plonk::commitment_scheme::PublicParameters;
+use microkelvin::{HostStore, OffsetLen};
+use rkyv::{check_archived_root, AlignedVec, Deserialize};
+use rusk_uplink::StoreContext;
 
 /// Builder for a [`NetworkState`].
 pub struct NetworkStateBuilder {
     store_and_contracts: Option<(StoreContext, Contracts)>,
     modules: HostModules,
-    id_path: Option<PathBuf>,
+    store_dir: Option<PathBuf>,
+    public_parameters: Option<Arc<PublicParameters>>,
     config: &'static Config,
 }
 
 impl NetworkStateBuilder {
-    const PERSISTENCE_ID_FILE_NAME: &'static str = "persist_id";
-
     /// Create a new [`NetworkState`] builder.
     pub fn new() -> Self {
         NetworkStateBuilder::default()
@@ -40,58 +43,157 @@ impl NetworkStateBuilder {
         Self {
             store_and_contracts: self.store_and_contracts,
             modules: self.modules,
-            id_path: self.id_path,
+            store_dir: self.store_dir,
+            public_parameters: self.public_parameters,
             config,
         }
     }
 
-    /// Set the directory to store the state. If not set,
+    /// Set the directory to store the state. If the directory holds a
+    /// persisted state, the latest one is restored.
+    ///
+    /// A state persisted with an older [`HASH_VERSION`] is migrated, and
+    /// recorded in place of the old one. Fails if the state was persisted
+    /// with a newer version.
     pub fn store_dir<P: AsRef<Path>>(self, dir: P) -> io::Result<Self> {
         let dir = dir.as_ref();
 
-        let id_path = dir.join(Self::PERSISTENCE_ID_FILE_NAME);
-
-        let store = StoreContext::new(HostStore::with_file(dir)?);
-        let contracts = match id_path.exists() && id_path.is_file() {
-            true => {
-                let buf = fs::read(&id_path)?;
-                let persist_id =
-                    unsafe { archived_root::<OffsetLen>(buf.as_slice()) };
-                let persist_id =
-                    persist_id.deserialize(&mut Infallible).unwrap();
-
-                let contracts_ident = Ident::<
-                    Hamt<ContractId, Contract, HashAnnotation, OffsetLen>,
-                    OffsetLen,
-                >::new(persist_id);
-
-                let contracts: &<Hamt<
-                    ContractId,
-                    Contract,
-                    HashAnnotation,
-                    OffsetLen,
-                > as Archive>::Archived = store.get::<Hamt<
-                    ContractId,
-                    Contract,
-                    HashAnnotation,
-                    OffsetLen,
-                >>(
-                    &contracts_ident
-                );
-
-                Contracts(contracts.deserialize(&mut store.clone()).unwrap())
+        let store = open_store(dir)?;
+        let contracts = match read_persist_id(dir)? {
+            Some(persist_id) => {
+                // The version of the state is only recorded in the log, so a
+                // store without one predates versioning.
+                let log = read_log(dir)?;
+                let version =
+                    log.last().map(PersistEntry::version).unwrap_or(0);
+
+                let contracts =
+                    Self::restore_contracts(&store, persist_id, version)?;
+                if version != HASH_VERSION {
+                    persist_migrated(
+                        dir,
+                        &store,
+                        &contracts,
+                        log.len().checked_sub(1),
+                    )?;
+                }
+                contracts
+            }
+            None => Contracts::default(),
+        };
+
+        Ok(Self {
+            store_and_contracts: Some((store, contracts)),
+            modules: self.modules,
+            store_dir: Some(dir.to_path_buf()),
+            public_parameters: self.public_parameters,
+            config: self.config,
+        })
+    }
+
+    /// Restore the most recently persisted state with the given `root` from
+    /// the directory set with [`store_dir`].
+    ///
+    /// States persisted with an older [`HASH_VERSION`] are migrated when
+    /// restored, resulting in a different root.
+    ///
+    /// [`store_dir`]: NetworkStateBuilder::store_dir
+    pub fn at_root(self, root: [u8; 32]) -> io::Result<Self> {
+        self.at_entry(|entry| entry.root() == root)
+    }
+
+    /// Restore the most recently persisted state with the given `label` from
+    /// the directory set with [`store_dir`].
+    ///
+    /// [`store_dir`]: NetworkStateBuilder::store_dir
+    pub fn at_label<S: AsRef<str>>(self, label: S) -> io::Result<Self> {
+        let label = label.as_ref();
+        self.at_entry(|entry| entry.label() == Some(label))
+    }
+
+    fn at_entry<F>(self, matches: F) -> io::Result<Self>
+    where
+        F: Fn(&PersistEntry) -> bool,
+    {
+        let (dir, store) = match (&self.store_dir, self.store_and_contracts) {
+            (Some(dir), Some((store, _))) => (dir, store),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "No store directory set",
+                ))
             }
-            false => Contracts::default(),
         };
 
+        let log = read_log(dir)?;
+        let index = log.iter().rposition(matches).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "No matching persisted state",
+            )
+        })?;
+        let entry = &log[index];
+
+        let contracts =
+            Self::restore_contracts(&store, entry.id(), entry.version())?;
+        if entry.version() != HASH_VERSION {
+            persist_migrated(dir, &store, &contracts, Some(index))?;
+        }
+
         Ok(Self {
             store_and_contracts: Some((store, contracts)),
             modules: self.modules,
-            id_path: Some(id_path),
+            store_dir: self.store_dir,
+            public_parameters: self.public_parameters,
             config: self.config,
         })
     }
 
+    /// Restores the contracts persisted with the given id, migrating them if
+    /// they were persisted with [`HASH_VERSION`] `0`.
+    ///
+    /// Fails if they were persisted with a newer version, since neither their
+    /// layout nor their annotations can be relied upon.
+    fn restore_contracts(
+        store: &StoreContext,
+        persist_id: OffsetLen,
+        version: u8,
+    ) -> io::Result<Contracts> {
+        if version > HASH_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Incompatible store version {}, expected {}",
+                    version, HASH_VERSION
+                ),
+            ));
+        }
+
+        // Copy the root node into an aligned buffer, since the stored bytes
+        // carry no alignment guarantees
+        let bytes = get_raw_checked(store, &persist_id).ok_or_else(|| {
+            PersistError::InvalidData(String::from(
+                "Persisted contracts past the end of the store",
+            ))
+        })?;
+        let mut root = AlignedVec::new();
+        root.extend_from_slice(bytes);
+
+        if version == 0 {
+            return Ok(migrate_v0(&root, store)?);
+        }
+
+        let contracts =
+            check_archived_root::<ContractsTree>(&root).map_err(|_| {
+                PersistError::InvalidData(String::from(
+                    "Invalid persisted contracts",
+                ))
+            })?;
+
+        let contracts = contracts.deserialize(&mut store.clone()).unwrap();
+        Ok(Contracts(contracts))
+    }
+
     /// Use the given host module.
     pub fn module<M>(self, module: M) -> Self
     where
@@ -103,7 +205,25 @@ impl NetworkStateBuilder {
         Self {
             store_and_contracts: self.store_and_contracts,
             modules,
-            id_path: self.id_path,
+            store_dir: self.store_dir,
+            public_parameters: self.public_parameters,
+            config: self.config,
+        }
+    }
+
+    /// Set the public parameters used to verify the proofs passed to the
+    /// `verify_proof` host function.
+    ///
+    /// Without them, calling `verify_proof` fails.
+    pub fn public_parameters(
+        self,
+        public_parameters: PublicParameters,
+    ) -> Self {
+        Self {
+            store_and_contracts: self.store_and_contracts,
+            modules: self.modules,
+            store_dir: self.store_dir,
+            public_parameters: Some(Arc::new(public_parameters)),
             config: self.config,
         }
     }
@@ -121,7 +241,8 @@ impl NetworkStateBuilder {
             contracts,
             modules: self.modules,
             store,
-            id_path: self.id_path,
+            store_dir: self.store_dir,
+            public_parameters: self.public_parameters,
             config: self.config,
         }
     }
@@ -132,7 +253,8 @@ impl Default for NetworkStateBuilder {
         Self {
             store_and_contracts: None,
             modules: HostModules::default(),
-            id_path: None,
+            store_dir: None,
+            public_parameters: None,
             config: &DEFAULT_CONFIG,
         }
     }
@@ -0,0 +1,286 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::Path;
+
+use bytecheck::CheckBytes;
+use microkelvin::{HostStore, OffsetLen, Store, Token, TokenBuffer};
+use rkyv::ser::{serializers::AllocSerializer, Serializer};
+use rkyv::{
+    check_archived_root, AlignedVec, Archive, Deserialize, Infallible,
+    Serialize,
+};
+use rusk_uplink::StoreContext;
+
+use crate::error::VMError;
+use crate::state::persist::{
+    read_log, read_persist_id, write_log, write_persist_id, PersistError,
+};
+use crate::state::NetworkState;
+
+/// Name of the file holding where the data of a compacted store was moved.
+const RELOCATIONS_FILE_NAME: &str = "relocations";
+
+/// Returned for reads of data dropped by a compaction.
+static ZEROES: [u8; u16::MAX as usize + 1] = [0; u16::MAX as usize + 1];
+
+/// A range of data moved by a compaction.
+#[derive(Debug, Clone, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+struct Relocation {
+    from: u64,
+    to: u64,
+    len: u16,
+}
+
+/// Where the data of a compacted store was moved.
+///
+/// The data keeps being addressed by the offsets it had before the
+/// compaction, since contracts hold on to them in their state. Data written
+/// after the compaction is addressed past the end of the store it was
+/// compacted from.
+#[derive(Debug, Clone, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+struct Relocations {
+    /// The moved ranges, ordered by their offset before the compaction.
+    moved: Vec<Relocation>,
+    /// The end of the store before the compaction.
+    compacted_end: u64,
+    /// The end of the compacted store.
+    end: u64,
+}
+
+impl Relocations {
+    /// Returns where the data at `ident` was moved to, or `None` if it was
+    /// dropped by the compaction.
+    fn locate(&self, ident: &OffsetLen) -> Option<OffsetLen> {
+        let (offset, len) = (ident.offset(), ident.len());
+
+        if offset >= self.compacted_end {
+            let offset = offset - self.compacted_end + self.end;
+            return Some(OffsetLen::new(offset, len));
+        }
+
+        let end = offset.checked_add(len as u64)?;
+        let after = self.moved.partition_point(|moved| moved.from <= offset);
+
+        // A range holding the data starts at most its maximum length before
+        self.moved[..after]
+            .iter()
+            .rev()
+            .take_while(|moved| offset - moved.from <= u16::MAX as u64)
+            .find(|moved| moved.from + moved.len as u64 >= end)
+            .map(|moved| OffsetLen::new(moved.to + offset - moved.from, len))
+    }
+
+    /// Returns the offset data written to the compacted store at `ident` is
+    /// addressed by.
+    fn address_written(&self, ident: OffsetLen) -> OffsetLen {
+        let offset = ident.offset() - self.end + self.compacted_end;
+        OffsetLen::new(offset, ident.len())
+    }
+}
+
+/// A compacted store, addressing its data as it was before the compaction.
+struct RelocatedStore {
+    inner: HostStore,
+    relocations: Relocations,
+}
+
+impl Store for RelocatedStore {
+    type Identifier = OffsetLen;
+
+    fn get(&self, ident: &OffsetLen) -> &[u8] {
+        match self.relocations.locate(ident) {
+            Some(moved) => self.inner.get(&moved),
+            None => &ZEROES[..ident.len() as usize],
+        }
+    }
+
+    fn request_buffer(&self) -> TokenBuffer {
+        self.inner.request_buffer()
+    }
+
+    fn persist(&self) -> Result<(), ()> {
+        self.inner.persist()
+    }
+
+    fn commit(&self, buffer: &mut TokenBuffer) -> Self::Identifier {
+        let ident = self.inner.commit(buffer);
+        self.relocations.address_written(ident)
+    }
+
+    fn extend(&self, buffer: &mut TokenBuffer) -> Result<(), ()> {
+        self.inner.extend(buffer)
+    }
+
+    fn return_token(&self, token: Token) {
+        self.inner.return_token(token)
+    }
+}
+
+/// Opens the store in the given directory, addressing its data as before
+/// it was compacted if it was.
+pub(crate) fn open_store(dir: &Path) -> io::Result<StoreContext> {
+    let store = HostStore::with_file(dir)?;
+
+    let relocations_path = dir.join(RELOCATIONS_FILE_NAME);
+    if !relocations_path.is_file() {
+        return Ok(StoreContext::new(store));
+    }
+
+    let mut buf = AlignedVec::new();
+    buf.extend_from_slice(&fs::read(relocations_path)?);
+    let relocations =
+        check_archived_root::<Relocations>(&buf).map_err(|_| {
+            PersistError::InvalidData(String::from("Invalid relocations"))
+        })?;
+    let relocations = relocations.deserialize(&mut Infallible).unwrap();
+
+    Ok(StoreContext::new(RelocatedStore {
+        inner: store,
+        relocations,
+    }))
+}
+
+/// Returns the ranges of the store, up to its `end`, reachable from the given
+/// roots.
+///
+/// Contracts reference the data they put in the store from within their
+/// state, in a layout only they know. Any archived [`OffsetLen`] found in
+/// reachable data - at any position - that lies within the store is then
+/// taken as a reference. This may keep some unreachable data, but never
+/// drops reachable data.
+fn reachable(
+    store: &StoreContext,
+    end: u64,
+    roots: impl IntoIterator<Item = OffsetLen>,
+) -> BTreeSet<(u64, u16)> {
+    const IDENT_LEN: usize = mem::size_of::<<OffsetLen as Archive>::Archived>();
+
+    let in_store = |offset: u64, len: u16| {
+        len > 0
+            && offset
+                .checked_add(len as u64)
+                .map_or(false, |ident_end| ident_end <= end)
+    };
+
+    let mut reached = BTreeSet::new();
+    let mut pending: Vec<_> = roots
+        .into_iter()
+        .map(|root| (root.offset(), root.len()))
+        .filter(|&(offset, len)| in_store(offset, len))
+        .collect();
+
+    let mut window = AlignedVec::with_capacity(IDENT_LEN);
+    while let Some((offset, len)) = pending.pop() {
+        if !reached.insert((offset, len)) {
+            continue;
+        }
+
+        let bytes = store.get_raw(&OffsetLen::new(offset, len));
+        for candidate in bytes.windows(IDENT_LEN) {
+            window.clear();
+            window.extend_from_slice(candidate);
+
+            if let Ok(ident) = check_archived_root::<OffsetLen>(&window) {
+                let ident: OffsetLen =
+                    ident.deserialize(&mut Infallible).unwrap();
+                let ident = (ident.offset(), ident.len());
+
+                if in_store(ident.0, ident.1) && !reached.contains(&ident) {
+                    pending.push(ident);
+                }
+            }
+        }
+    }
+
+    reached
+}
+
+impl NetworkState {
+    /// Compacts the store in the `source` directory into the `target`
+    /// directory, which must not hold a store already.
+    ///
+    /// Only the data reachable from the states recorded in the log of
+    /// persisted states - and from the latest persisted state - is copied,
+    /// leaving out the data of states that were never persisted. Every
+    /// recorded state can be restored from the compacted store, with the same
+    /// root.
+    ///
+    /// The store must not be in use while being compacted.
+    pub fn compact<P, Q>(source: P, target: Q) -> Result<(), VMError>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let (source, target) = (source.as_ref(), target.as_ref());
+
+        let log = read_log(source)?;
+        let persist_id = read_persist_id(source)?;
+
+        fs::create_dir_all(target).map_err(PersistError::Io)?;
+        if read_persist_id(target)?.is_some() {
+            return Err(PersistError::Io(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "The target directory already holds a store",
+            ))
+            .into());
+        }
+
+        let store = open_store(source).map_err(PersistError::Io)?;
+        let compacted_end = store.put_raw(&[]).offset();
+        let roots = log.iter().map(|entry| entry.id()).chain(persist_id);
+
+        let compacted =
+            HostStore::with_file(target).map_err(PersistError::Io)?;
+        let compacted = StoreContext::new(compacted);
+
+        let moved = reachable(&store, compacted_end, roots)
+            .into_iter()
+            .map(|(offset, len)| {
+                let bytes = store.get_raw(&OffsetLen::new(offset, len));
+                Relocation {
+                    from: offset,
+                    to: compacted.put_raw(bytes).offset(),
+                    len,
+                }
+            })
+            .collect();
+
+        compacted.persist().map_err(|_| {
+            PersistError::Store(String::from(
+                "Store persistence failed for compacted store",
+            ))
+        })?;
+
+        let relocations = Relocations {
+            moved,
+            compacted_end,
+            end: compacted.put_raw(&[]).offset(),
+        };
+
+        let mut serializer = AllocSerializer::<0>::default();
+        serializer.serialize_value(&relocations).unwrap();
+        let bytes = serializer.into_serializer().into_inner();
+        fs::write(target.join(RELOCATIONS_FILE_NAME), bytes)
+            .map_err(PersistError::Io)?;
+
+        // The states keep their identities, being addressed as before
+        if !log.is_empty() {
+            write_log(target, &log).map_err(PersistError::Io)?;
+        }
+        if let Some(persist_id) = persist_id {
+            write_persist_id(target, &persist_id).map_err(PersistError::Io)?;
+        }
+
+        Ok(())
+    }
+}
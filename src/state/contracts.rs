@@ -5,20 +5,39 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use crate::config::Config;
-use crate::contract::Contract;
+use crate::contract::{Contract, LegacyContract, StorageKey};
 use crate::error::VMError;
 use crate::modules::compile_module;
 use crate::state::hash::{hash, Hasher};
 
-use rusk_uplink::ContractId;
+use rusk_uplink::{
+    combine_hash, leaf_hash, tree_slots, ContractId, ProofLevel, StateProof,
+    LEAF_TAG,
+};
 
 use bytecheck::CheckBytes;
 use dusk_hamt::{Hamt, KvPair, Lookup};
 use microkelvin::{
-    Annotation, BranchRef, BranchRefMut, Combine, Keyed, OffsetLen,
+    Annotation, BranchRef, BranchRefMut, Child, Combine, Compound, Keyed,
+    MaybeStored, OffsetLen,
 };
 use rkyv::{Archive, Deserialize, Serialize};
 
+/// Version of the format of persisted states, covering both the archived
+/// layout of [`Contract`]s and the scheme used to compute the
+/// [`HashAnnotation`]s persisted along with them.
+///
+/// - `0`: stores persisted before versioning, whose leaves commit to the
+///   contract's id and state.
+/// - `1`: leaves also commit to the hash of the contract's bytecode, the root
+///   of its storage and its nonce, and contracts carry their storage, nonce and
+///   code hash.
+///
+/// Stores persisted with version `0` are migrated when restored, rehashing
+/// their leaves and recording the migrated state in place of the old one.
+/// Stores persisted with a newer version cannot be restored.
+pub const HASH_VERSION: u8 = 1;
+
 #[derive(
     Default,
     Debug,
@@ -33,16 +52,35 @@ use rkyv::{Archive, Deserialize, Serialize};
 #[archive(as = "Self")]
 pub struct HashAnnotation([u8; 32]);
 
+impl From<HashAnnotation> for [u8; 32] {
+    fn from(annotation: HashAnnotation) -> Self {
+        annotation.0
+    }
+}
+
 impl Combine<HashAnnotation> for HashAnnotation {
     fn combine(&mut self, with: &HashAnnotation) {
-        let mut hasher = Hasher::new();
-        hasher.update(self.0).update(with.0);
-        self.0 = hasher.finalize();
+        self.0 = combine_hash(&self.0, &with.0);
     }
 }
 
 impl Annotation<KvPair<ContractId, Contract>> for HashAnnotation {
     fn from_leaf(leaf: &KvPair<ContractId, Contract>) -> Self {
+        let contract = leaf.value();
+        Self(leaf_hash(
+            leaf.key(),
+            contract.code_hash(),
+            &contract.storage_root(),
+            contract.nonce(),
+            contract.state(),
+        ))
+    }
+}
+
+/// Leaves as hashed with [`HASH_VERSION`] `0`, committing only to the
+/// contract's id and state.
+impl Annotation<KvPair<ContractId, LegacyContract>> for HashAnnotation {
+    fn from_leaf(leaf: &KvPair<ContractId, LegacyContract>) -> Self {
         let mut hasher = Hasher::new();
         hasher
             .update(leaf.key().as_bytes())
@@ -51,11 +89,94 @@ impl Annotation<KvPair<ContractId, Contract>> for HashAnnotation {
     }
 }
 
+impl Annotation<KvPair<StorageKey, Vec<u8>>> for HashAnnotation {
+    fn from_leaf(leaf: &KvPair<StorageKey, Vec<u8>>) -> Self {
+        let mut hasher = Hasher::new();
+        hasher
+            .update([LEAF_TAG])
+            .update(leaf.key())
+            .update(leaf.value());
+        Self(hasher.finalize())
+    }
+}
+
+/// Derives the id of a contract deployed from the host, given its bytecode,
+/// initial state and a `salt`.
+fn derive_deploy_id(contract: &Contract, salt: &[u8]) -> ContractId {
+    let mut hasher = Hasher::new();
+    hasher
+        .update(contract.code_hash())
+        .update(hash(contract.state()))
+        .update(salt);
+    hasher.finalize().into()
+}
+
+/// Derives the id of the contract deployed by `deployer` with the given
+/// `nonce`.
+pub(crate) fn derive_contract_id(
+    deployer: &ContractId,
+    nonce: u64,
+) -> ContractId {
+    let mut hasher = Hasher::new();
+    hasher
+        .update(deployer.as_bytes())
+        .update(nonce.to_le_bytes());
+    hasher.finalize().into()
+}
+
+pub(crate) type ContractsTree =
+    Hamt<ContractId, Contract, HashAnnotation, OffsetLen>;
+
+/// Pushes the levels of the path to the contract with the given id in `node`,
+/// at `depth` in the tree, to `levels`, from the leaf up. Returns false if the
+/// contract is not in the subtree.
+fn prove_node(
+    node: &ContractsTree,
+    contract_id: &ContractId,
+    depth: usize,
+    levels: &mut Vec<ProofLevel>,
+) -> bool {
+    let mut level = ProofLevel::default();
+    let mut path = None;
+
+    for i in 0.. {
+        let hash = match node.child(i) {
+            Child::Leaf(leaf) if leaf.key() == contract_id => {
+                path = Some(i);
+                continue;
+            }
+            Child::Leaf(leaf) => HashAnnotation::from_leaf(leaf).0,
+            Child::Link(link) => {
+                if path.is_none() {
+                    if let MaybeStored::Memory(child) = link.inner() {
+                        if prove_node(child, contract_id, depth + 1, levels) {
+                            path = Some(i);
+                            continue;
+                        }
+                    }
+                }
+                link.annotation().0
+            }
+            Child::Empty => continue,
+            Child::End => break,
+        };
+
+        level.siblings[i] = Some(hash);
+    }
+
+    match path {
+        Some(slot) => {
+            debug_assert_eq!(slot, tree_slots(contract_id)[depth]);
+            levels.push(level);
+            true
+        }
+        None => false,
+    }
+}
+
 /// State of the contracts on the network.
 #[derive(Archive, Default, Clone)]
-pub struct Contracts(
-    pub(crate) Hamt<ContractId, Contract, HashAnnotation, OffsetLen>,
-);
+pub struct Contracts(pub(crate) ContractsTree);
 
 impl Contracts {
     /// Root hash
@@ -63,6 +184,38 @@ impl Contracts {
         HashAnnotation::from_node(&self.0).0
     }
 
+    /// Returns a proof of the inclusion of the specified contract's state in
+    /// the root.
+    pub fn prove_contract(
+        &self,
+        contract_id: &ContractId,
+    ) -> Result<StateProof, VMError> {
+        // Walking mutably to the contract brings all the nodes on its path
+        // into memory, while the rest of the tree is left untouched.
+        let mut contracts = self.0.clone();
+        let (code_hash, storage_root, nonce) = {
+            let mut contract = contracts
+                .get_mut(contract_id)
+                .ok_or(VMError::UnknownContract(*contract_id))?;
+            let contract = contract.leaf_mut();
+            (
+                *contract.code_hash(),
+                contract.storage_root(),
+                contract.nonce(),
+            )
+        };
+
+        let mut levels = vec![];
+        prove_node(&contracts, contract_id, 0, &mut levels);
+
+        Ok(StateProof {
+            code_hash,
+            storage_root,
+            nonce,
+            levels,
+        })
+    }
+
     /// Returns a reference to the specified contracts state.
     pub fn get_contract(
         &self,
@@ -85,26 +238,81 @@ impl Contracts {
 
     /// Deploys a contract to the state, returning the address of the created
     /// contract or an error.
+    ///
+    /// The address is derived from the contract's bytecode and initial state.
     pub fn deploy(
         &mut self,
         contract: Contract,
         config: &'static Config,
     ) -> Result<ContractId, VMError> {
-        let id: ContractId = hash(contract.bytecode()).into();
+        self.deploy_with_salt(contract, &[], config)
+    }
+
+    /// Deploys a contract to the state, returning the address of the created
+    /// contract or an error.
+    ///
+    /// The address is derived from the contract's bytecode, initial state and
+    /// the given `salt`, allowing for the same contract to be deployed more
+    /// than once.
+    pub fn deploy_with_salt(
+        &mut self,
+        contract: Contract,
+        salt: &[u8],
+        config: &'static Config,
+    ) -> Result<ContractId, VMError> {
+        let id = derive_deploy_id(&contract, salt);
         self.deploy_with_id(id, contract, config)
     }
 
+    /// Removes the contract with the given id from the state, returning it.
+    pub(crate) fn remove_contract(
+        &mut self,
+        contract_id: &ContractId,
+    ) -> Result<Contract, VMError> {
+        self.0
+            .remove(contract_id)
+            .ok_or(VMError::UnknownContract(*contract_id))
+    }
+
     /// Deploys a contract with the given id to the state.
+    ///
+    /// Fails with [`VMError::ContractAlreadyExists`] if there already is a
+    /// contract with the given id.
     pub fn deploy_with_id(
         &mut self,
         id: ContractId,
         contract: Contract,
         config: &'static Config,
     ) -> Result<ContractId, VMError> {
+        if self.0.get(&id).is_some() {
+            return Err(VMError::ContractAlreadyExists(id));
+        }
+
         compile_module(contract.bytecode(), config)?;
 
         self.0.insert(id, contract);
 
         Ok(id)
     }
+
+    /// Replaces the contract with the given id, returning the previous one.
+    ///
+    /// Fails with [`VMError::UnknownContract`] if there is no contract with
+    /// the given id.
+    pub fn replace_contract(
+        &mut self,
+        id: ContractId,
+        mut contract: Contract,
+        config: &'static Config,
+    ) -> Result<Contract, VMError> {
+        compile_module(contract.bytecode(), config)?;
+
+        // The nonce is carried over, so that the contract does not deploy to
+        // the ids of the contracts it deployed before being replaced
+        let previous = self.remove_contract(&id)?;
+        contract.set_nonce(previous.nonce());
+        self.0.insert(id, contract);
+
+        Ok(previous)
+    }
 }
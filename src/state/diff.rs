@@ -0,0 +1,196 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+use microkelvin::{Child, Compound, Link, MaybeStored, OffsetLen};
+use rkyv::Deserialize;
+use rusk_uplink::{ContractId, StoreContext};
+
+use crate::contract::Contract;
+use crate::state::contracts::{ContractsTree, HashAnnotation};
+use crate::state::NetworkState;
+
+/// A change to a contract between two network states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContractChange {
+    /// The contract was deployed
+    Added(ContractId),
+    /// The contract was removed
+    Removed(ContractId),
+    /// The state or storage of the contract changed
+    StateChanged(ContractId),
+    /// The bytecode of the contract changed, possibly along with its state
+    CodeChanged(ContractId),
+    /// Only the nonce of the contract changed, as when the contracts it
+    /// deployed were removed since
+    NonceChanged(ContractId),
+}
+
+impl ContractChange {
+    /// The id of the changed contract.
+    pub fn contract_id(&self) -> &ContractId {
+        match self {
+            ContractChange::Added(id)
+            | ContractChange::Removed(id)
+            | ContractChange::StateChanged(id)
+            | ContractChange::CodeChanged(id)
+            | ContractChange::NonceChanged(id) => id,
+        }
+    }
+}
+
+impl NetworkState {
+    /// Returns the changes to the contracts needed to go from this state to
+    /// `other`.
+    ///
+    /// Both contract trees are walked together, skipping the subtrees that
+    /// have the same root, making the diff between a state and a fork of it
+    /// proportional to the number of changed contracts.
+    pub fn diff(&self, other: &NetworkState) -> Vec<ContractChange> {
+        let mut changes = vec![];
+
+        if self.root() != other.root() {
+            let from = Side::new(&self.contracts.0, &self.store);
+            let to = Side::new(&other.contracts.0, &other.store);
+            diff_nodes(&from, &to, &mut changes);
+        }
+
+        changes
+    }
+}
+
+/// A node of the contract tree of one of the states being compared, along
+/// with the store backing it.
+struct Side<'a> {
+    node: Cow<'a, ContractsTree>,
+    store: &'a StoreContext,
+}
+
+impl<'a> Side<'a> {
+    fn new(node: &'a ContractsTree, store: &'a StoreContext) -> Self {
+        Side {
+            node: Cow::Borrowed(node),
+            store,
+        }
+    }
+
+    /// Returns the node pointed to by `link`, loading it from the store if
+    /// necessary.
+    fn child<'b>(
+        &'b self,
+        link: &'b Link<ContractsTree, HashAnnotation, OffsetLen>,
+    ) -> Side<'b> {
+        let node = match link.inner() {
+            MaybeStored::Memory(node) => Cow::Borrowed(node),
+            MaybeStored::Stored(stored) => Cow::Owned(
+                stored
+                    .inner()
+                    .deserialize(&mut self.store.clone())
+                    .expect("Infallible"),
+            ),
+        };
+
+        Side {
+            node,
+            store: self.store,
+        }
+    }
+
+    /// Collects all the contracts in the subtree rooted at `child`.
+    fn collect(
+        &self,
+        child: Child<ContractsTree, HashAnnotation, OffsetLen>,
+        contracts: &mut Vec<(ContractId, Contract)>,
+    ) {
+        match child {
+            Child::Leaf(leaf) => {
+                contracts.push((*leaf.key(), leaf.value().clone()))
+            }
+            Child::Link(link) => {
+                let node = self.child(link);
+                for i in 0.. {
+                    match node.node.child(i) {
+                        Child::End => break,
+                        child => node.collect(child, contracts),
+                    }
+                }
+            }
+            Child::Empty | Child::End => (),
+        }
+    }
+}
+
+/// Walks two nodes at the same position in their trees, pushing the changes
+/// between them.
+fn diff_nodes(from: &Side, to: &Side, changes: &mut Vec<ContractChange>) {
+    for i in 0.. {
+        match (from.node.child(i), to.node.child(i)) {
+            (Child::End, _) | (_, Child::End) => break,
+            (Child::Empty, Child::Empty) => (),
+            (Child::Link(a), Child::Link(b)) => {
+                if a.annotation().0 != b.annotation().0 {
+                    diff_nodes(&from.child(a), &to.child(b), changes);
+                }
+            }
+            (a, b) => {
+                let mut from_contracts = vec![];
+                let mut to_contracts = vec![];
+                from.collect(a, &mut from_contracts);
+                to.collect(b, &mut to_contracts);
+                diff_contracts(from_contracts, to_contracts, changes);
+            }
+        }
+    }
+}
+
+/// Compares two sets of contracts, pushing the changes between them.
+fn diff_contracts(
+    mut from: Vec<(ContractId, Contract)>,
+    mut to: Vec<(ContractId, Contract)>,
+    changes: &mut Vec<ContractChange>,
+) {
+    from.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+    to.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+
+    let mut from = from.into_iter().peekable();
+    let mut to = to.into_iter().peekable();
+
+    loop {
+        let ordering = match (from.peek(), to.peek()) {
+            (None, None) => break,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some((a, _)), Some((b, _))) => a.as_bytes().cmp(b.as_bytes()),
+        };
+
+        match ordering {
+            Ordering::Less => {
+                let (id, _) = from.next().expect("Peeked");
+                changes.push(ContractChange::Removed(id));
+            }
+            Ordering::Greater => {
+                let (id, _) = to.next().expect("Peeked");
+                changes.push(ContractChange::Added(id));
+            }
+            Ordering::Equal => {
+                let (id, a) = from.next().expect("Peeked");
+                let (_, b) = to.next().expect("Peeked");
+
+                if a.code_hash() != b.code_hash() {
+                    changes.push(ContractChange::CodeChanged(id));
+                } else if a.state() != b.state()
+                    || a.storage_root() != b.storage_root()
+                {
+                    changes.push(ContractChange::StateChanged(id));
+                } else if a.nonce() != b.nonce() {
+                    changes.push(ContractChange::NonceChanged(id));
+                }
+            }
+        }
+    }
+}
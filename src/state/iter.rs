@@ -0,0 +1,197 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use microkelvin::{Child, Compound, Link, MaybeStored, OffsetLen};
+use rkyv::Deserialize;
+use rusk_uplink::{tree_slots, ContractId, StoreContext};
+
+use crate::contract::Contract;
+use crate::state::contracts::{ContractsTree, HashAnnotation};
+use crate::state::NetworkState;
+
+/// Information about a deployed contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractInfo {
+    code_hash: [u8; 32],
+    code_size: usize,
+    state_size: usize,
+}
+
+impl ContractInfo {
+    fn new(contract: &Contract) -> Self {
+        Self {
+            code_hash: *contract.code_hash(),
+            code_size: contract.bytecode().len(),
+            state_size: contract.state().len(),
+        }
+    }
+
+    /// The hash of the contract's bytecode.
+    pub fn code_hash(&self) -> &[u8; 32] {
+        &self.code_hash
+    }
+
+    /// The size of the contract's bytecode in bytes.
+    pub fn code_size(&self) -> usize {
+        self.code_size
+    }
+
+    /// The size of the contract's state in bytes.
+    pub fn state_size(&self) -> usize {
+        self.state_size
+    }
+}
+
+/// Iterator over the contracts deployed in a [`NetworkState`].
+///
+/// Nodes of the contracts tree are only loaded from the store as they are
+/// reached.
+pub struct ContractsIter<'a> {
+    stack: Vec<(ContractsTree, usize)>,
+    store: &'a StoreContext,
+}
+
+impl<'a> ContractsIter<'a> {
+    fn new(root: &ContractsTree, store: &'a StoreContext) -> Self {
+        Self {
+            stack: vec![(root.clone(), 0)],
+            store,
+        }
+    }
+
+    /// Creates an iterator starting right after `start` in the order of the
+    /// tree, descending along its path without visiting the contracts before
+    /// it.
+    ///
+    /// The order of the tree is the order of the slots along the paths of
+    /// the contracts, so this works whether or not `start` is in the tree.
+    fn after(
+        root: &ContractsTree,
+        start: &ContractId,
+        store: &'a StoreContext,
+    ) -> Self {
+        let slots = tree_slots(start);
+
+        let mut stack = vec![];
+        let mut node = root.clone();
+
+        for (depth, slot) in slots.iter().copied().enumerate() {
+            let step = match node.child(slot) {
+                Child::Link(link) => Step::Descend(load(link, store)),
+                // Another contract in the slot comes after `start` if its
+                // path does
+                Child::Leaf(leaf)
+                    if leaf.key() != start
+                        && tree_slots(leaf.key())[depth + 1..]
+                            > slots[depth + 1..] =>
+                {
+                    Step::Include
+                }
+                _ => Step::Skip,
+            };
+
+            match step {
+                Step::Descend(next) => {
+                    stack.push((node, slot + 1));
+                    node = next;
+                }
+                Step::Include => {
+                    stack.push((node, slot));
+                    break;
+                }
+                Step::Skip => {
+                    stack.push((node, slot + 1));
+                    break;
+                }
+            }
+        }
+
+        Self { stack, store }
+    }
+
+    fn next_contract(&mut self) -> Option<(ContractId, Contract)> {
+        loop {
+            let (node, ofs) = self.stack.last_mut()?;
+            let i = *ofs;
+            *ofs += 1;
+
+            let next = match node.child(i) {
+                Child::Leaf(leaf) => {
+                    return Some((*leaf.key(), leaf.value().clone()));
+                }
+                Child::Link(link) => load(link, self.store),
+                Child::Empty => continue,
+                Child::End => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+
+            self.stack.push((next, 0));
+        }
+    }
+}
+
+/// A step taken when seeking through the tree.
+enum Step {
+    /// Descend into the child node
+    Descend(ContractsTree),
+    /// Start at the child
+    Include,
+    /// Start after the child
+    Skip,
+}
+
+/// Loads the node behind a link, from the store if needed.
+fn load(
+    link: &Link<ContractsTree, HashAnnotation, OffsetLen>,
+    store: &StoreContext,
+) -> ContractsTree {
+    match link.inner() {
+        MaybeStored::Memory(node) => node.clone(),
+        MaybeStored::Stored(stored) => stored
+            .inner()
+            .deserialize(&mut store.clone())
+            .expect("Infallible"),
+    }
+}
+
+impl<'a> Iterator for ContractsIter<'a> {
+    type Item = (ContractId, ContractInfo);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_contract()
+            .map(|(id, contract)| (id, ContractInfo::new(&contract)))
+    }
+}
+
+impl NetworkState {
+    /// Returns an iterator over all the contracts deployed in the state,
+    /// along with information about them.
+    ///
+    /// Contracts are returned in the order of the underlying tree, which is
+    /// the same for any two states with the same contracts.
+    pub fn contracts(&self) -> ContractsIter {
+        ContractsIter::new(&self.contracts.0, &self.store)
+    }
+
+    /// Returns an iterator over the contracts deployed in the state that
+    /// come after `start` in the order of [`contracts`].
+    ///
+    /// Allows for paging through the contracts, using the last contract of a
+    /// page as the start of the next one. The iterator seeks directly to
+    /// `start`, so paging only loads the nodes along its path and the ones
+    /// after it.
+    ///
+    /// If there is no contract with the given id - for instance because it
+    /// was removed between two pages - the iterator starts at the contract
+    /// that would follow it.
+    ///
+    /// [`contracts`]: NetworkState::contracts
+    pub fn contracts_after(&self, start: &ContractId) -> ContractsIter {
+        ContractsIter::after(&self.contracts.0, start, &self.store)
+    }
+}
@@ -0,0 +1,75 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use dusk_hamt::Hamt;
+use microkelvin::{Child, Compound, Keyed, Link, MaybeStored, OffsetLen};
+use rkyv::{check_archived_root, Deserialize};
+use rusk_uplink::{ContractId, StoreContext};
+
+use crate::contract::LegacyContract;
+use crate::state::contracts::{ContractsTree, HashAnnotation};
+use crate::state::persist::PersistError;
+use crate::state::Contracts;
+
+/// The contracts tree as persisted with [`HASH_VERSION`] `0`.
+///
+/// [`HASH_VERSION`]: crate::HASH_VERSION
+type LegacyTree = Hamt<ContractId, LegacyContract, HashAnnotation, OffsetLen>;
+
+/// Migrates the contracts persisted with [`HASH_VERSION`] `0` in `root`,
+/// rehashing every leaf to commit to the contract's bytecode, storage root and
+/// nonce.
+///
+/// [`HASH_VERSION`]: crate::HASH_VERSION
+pub(crate) fn migrate_v0(
+    root: &[u8],
+    store: &StoreContext,
+) -> Result<Contracts, PersistError> {
+    let legacy = check_archived_root::<LegacyTree>(root).map_err(|_| {
+        PersistError::InvalidData(String::from("Invalid persisted contracts"))
+    })?;
+    let legacy: LegacyTree = legacy.deserialize(&mut store.clone()).unwrap();
+
+    let mut contracts = ContractsTree::new();
+    migrate_node(&legacy, store, &mut contracts);
+
+    Ok(Contracts(contracts))
+}
+
+/// Inserts the contracts in `node` and its children into `contracts`,
+/// loading the children from the store as they are reached.
+fn migrate_node(
+    node: &LegacyTree,
+    store: &StoreContext,
+    contracts: &mut ContractsTree,
+) {
+    for i in 0.. {
+        match node.child(i) {
+            Child::Leaf(leaf) => {
+                contracts.insert(*leaf.key(), leaf.value().migrate());
+            }
+            Child::Link(link) => {
+                migrate_node(&load(link, store), store, contracts)
+            }
+            Child::Empty => continue,
+            Child::End => break,
+        }
+    }
+}
+
+/// Loads the node behind a link, from the store if needed.
+fn load(
+    link: &Link<LegacyTree, HashAnnotation, OffsetLen>,
+    store: &StoreContext,
+) -> LegacyTree {
+    match link.inner() {
+        MaybeStored::Memory(node) => node.clone(),
+        MaybeStored::Stored(stored) => stored
+            .inner()
+            .deserialize(&mut store.clone())
+            .expect("Infallible"),
+    }
+}
@@ -4,13 +4,27 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use bytecheck::CheckBytes;
+use microkelvin::OffsetLen;
 use rkyv::ser::{serializers::AllocSerializer, Serializer};
-use std::fs;
-use std::io;
+use rkyv::{
+    check_archived_root, AlignedVec, Archive, Deserialize, Infallible,
+    Serialize,
+};
+use rusk_uplink::StoreContext;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
 use thiserror::Error;
 
 use crate::error::VMError;
-use crate::state::NetworkState;
+use crate::state::contracts::HASH_VERSION;
+use crate::state::{Contracts, NetworkState};
+
+/// Name of the file holding the identity of the latest persisted state.
+pub(crate) const PERSISTENCE_ID_FILE_NAME: &str = "persist_id";
+/// Name of the file holding the log of all persisted states.
+pub(crate) const PERSISTENCE_LOG_FILE_NAME: &str = "persist_log";
 
 /// An error that can happen when persisting structures to disk
 #[derive(Error, Debug)]
@@ -21,27 +35,254 @@ pub enum PersistError {
     /// Store persistence error
     #[error("{0}")]
     Store(String),
+    /// Persisted data failed validation when read back
+    #[error("Invalid persisted data: {0}")]
+    InvalidData(String),
+}
+
+impl From<PersistError> for io::Error {
+    fn from(err: PersistError) -> Self {
+        match err {
+            PersistError::Io(err) => err,
+            err => io::Error::new(io::ErrorKind::InvalidData, err),
+        }
+    }
+}
+
+/// An entry in the persistence log, recording a persisted state.
+#[derive(Debug, Clone, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct PersistEntry {
+    label: Option<String>,
+    root: [u8; 32],
+    version: u8,
+    id: OffsetLen,
+}
+
+impl PersistEntry {
+    /// The label the state was persisted with, if any.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// The root of the persisted state.
+    pub fn root(&self) -> [u8; 32] {
+        self.root
+    }
+
+    /// The [`HASH_VERSION`] the state was persisted with.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// The identity of the persisted contracts in the store.
+    pub(crate) fn id(&self) -> OffsetLen {
+        self.id
+    }
+}
+
+/// Reads all the entries of the persistence log in the given directory, in
+/// the order they were persisted.
+pub(crate) fn read_log(dir: &Path) -> Result<Vec<PersistEntry>, PersistError> {
+    let log_path = dir.join(PERSISTENCE_LOG_FILE_NAME);
+    if !log_path.is_file() {
+        return Ok(vec![]);
+    }
+
+    let log = fs::read(log_path)?;
+    let mut entries = vec![];
+
+    let mut log = log.as_slice();
+    while !log.is_empty() {
+        let invalid_log =
+            || PersistError::InvalidData(String::from("Invalid log entry"));
+
+        if log.len() < 4 {
+            return Err(invalid_log());
+        }
+        let mut len = [0u8; 4];
+        len.copy_from_slice(&log[..4]);
+        let len = u32::from_le_bytes(len) as usize;
+
+        let bytes = log.get(4..4 + len).ok_or_else(invalid_log)?;
+        let mut aligned = AlignedVec::new();
+        aligned.extend_from_slice(bytes);
+
+        let entry = check_archived_root::<PersistEntry>(&aligned)
+            .map_err(|_| invalid_log())?;
+        entries.push(entry.deserialize(&mut Infallible).unwrap());
+
+        log = &log[4 + len..];
+    }
+
+    Ok(entries)
+}
+
+/// Encodes an entry as a record of the persistence log.
+fn log_record(entry: &PersistEntry) -> Vec<u8> {
+    let mut serializer = AllocSerializer::<0>::default();
+    serializer.serialize_value(entry).unwrap();
+    let bytes = serializer.into_serializer().into_inner();
+
+    let mut record = Vec::with_capacity(4 + bytes.len());
+    record.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    record.extend_from_slice(&bytes);
+    record
+}
+
+/// Appends an entry to the persistence log in the given directory.
+fn append_log(dir: &Path, entry: &PersistEntry) -> io::Result<()> {
+    let mut log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(PERSISTENCE_LOG_FILE_NAME))?;
+
+    log.write_all(&log_record(entry))
+}
+
+/// Replaces the persistence log in the given directory with `entries`.
+///
+/// The log is written aside and moved in place, so that it is never left
+/// half written.
+pub(crate) fn write_log(
+    dir: &Path,
+    entries: &[PersistEntry],
+) -> io::Result<()> {
+    let log: Vec<u8> = entries.iter().flat_map(log_record).collect();
+
+    let log_path = dir.join(PERSISTENCE_LOG_FILE_NAME);
+    let tmp_path = log_path.with_extension("tmp");
+    fs::write(&tmp_path, log)?;
+    fs::rename(tmp_path, log_path)
+}
+
+/// Reads the identity of the latest persisted state in the given directory, if
+/// there is one.
+pub(crate) fn read_persist_id(
+    dir: &Path,
+) -> Result<Option<OffsetLen>, PersistError> {
+    let id_path = dir.join(PERSISTENCE_ID_FILE_NAME);
+    if !id_path.is_file() {
+        return Ok(None);
+    }
+
+    let mut buf = AlignedVec::new();
+    buf.extend_from_slice(&fs::read(&id_path)?);
+    let persist_id = check_archived_root::<OffsetLen>(&buf).map_err(|_| {
+        PersistError::InvalidData(String::from("Invalid persistence id"))
+    })?;
+
+    Ok(Some(persist_id.deserialize(&mut Infallible).unwrap()))
+}
+
+/// Writes the identity of the latest persisted state in the given directory.
+pub(crate) fn write_persist_id(dir: &Path, id: &OffsetLen) -> io::Result<()> {
+    let mut serializer = AllocSerializer::<0>::default();
+    serializer.serialize_value(id).unwrap();
+    let bytes = serializer.into_serializer().into_inner();
+
+    fs::write(dir.join(PERSISTENCE_ID_FILE_NAME), bytes)
+}
+
+/// Persists `contracts` to the store, returning their identity.
+fn persist_contracts(
+    store: &StoreContext,
+    contracts: &Contracts,
+) -> Result<OffsetLen, PersistError> {
+    let contracts_stored = store.store(&contracts.0);
+    store.persist().map_err(|_| {
+        PersistError::Store(String::from(
+            "Store persistence failed for network state",
+        ))
+    })?;
+
+    Ok(*contracts_stored.ident().erase())
+}
+
+/// Persists contracts migrated from an older [`HASH_VERSION`], recording
+/// them in place of the state they were migrated from.
+///
+/// The state is the one logged at `index`, or for stores predating the log
+/// the one its persistence id points to - in which case the log is started
+/// with the migrated state. If the state is the latest one, the persistence
+/// id is rewritten to point to the migrated state.
+pub(crate) fn persist_migrated(
+    dir: &Path,
+    store: &StoreContext,
+    contracts: &Contracts,
+    index: Option<usize>,
+) -> Result<(), PersistError> {
+    let id = persist_contracts(store, contracts)?;
+
+    let mut entries = read_log(dir)?;
+    let latest = match index {
+        Some(index) => index + 1 == entries.len(),
+        None => true,
+    };
+
+    let label = index.and_then(|index| entries[index].label.take());
+    let entry = PersistEntry {
+        label,
+        root: contracts.root(),
+        version: HASH_VERSION,
+        id,
+    };
+    match index {
+        Some(index) => entries[index] = entry,
+        None => entries.push(entry),
+    }
+    write_log(dir, &entries)?;
+
+    if latest {
+        write_persist_id(dir, &id)?;
+    }
+
+    Ok(())
 }
 
 impl NetworkState {
     /// Persists the contracts in the [`NetworkState`].
+    ///
+    /// If the state is backed by a directory the persisted state is recorded
+    /// in its log, and is the one reopened by default.
     pub fn persist(&self) -> Result<(), VMError> {
-        let store = &self.store;
-        let contracts_stored = store.store(&self.contracts.0);
-        store.persist().map_err(|_| {
-            PersistError::Store(String::from(
-                "Store persistence failed for network state",
-            ))
-        })?;
-
-        if let Some(id_path) = &self.id_path {
-            let persistence_id = *contracts_stored.ident().erase();
-
-            let mut serializer = AllocSerializer::<0>::default();
-            serializer.serialize_value(&persistence_id).unwrap();
-            let bytes = serializer.into_serializer().into_inner();
-
-            fs::write(id_path, bytes).map_err(PersistError::Io)?;
+        self.persist_entry(None)
+    }
+
+    /// Persists the contracts in the [`NetworkState`] under the given
+    /// `label`.
+    ///
+    /// The state can later be reopened using `at_label` on the
+    /// [`builder`], which picks the most recent state persisted with the
+    /// label.
+    ///
+    /// [`builder`]: NetworkState::builder
+    pub fn persist_as<S: Into<String>>(&self, label: S) -> Result<(), VMError> {
+        self.persist_entry(Some(label.into()))
+    }
+
+    /// Returns all the states persisted in the directory backing the
+    /// [`NetworkState`], in the order they were persisted.
+    pub fn persisted(&self) -> Result<Vec<PersistEntry>, VMError> {
+        match &self.store_dir {
+            Some(dir) => Ok(read_log(dir)?),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn persist_entry(&self, label: Option<String>) -> Result<(), VMError> {
+        let persistence_id = persist_contracts(&self.store, &self.contracts)?;
+
+        if let Some(dir) = &self.store_dir {
+            write_persist_id(dir, &persistence_id).map_err(PersistError::Io)?;
+
+            let entry = PersistEntry {
+                label,
+                root: self.root(),
+                version: HASH_VERSION,
+                id: persistence_id,
+            };
+            append_log(dir, &entry).map_err(PersistError::Io)?;
         }
 
         Ok(())
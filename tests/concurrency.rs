@@ -0,0 +1,90 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::sync::Arc;
+use std::thread;
+
+use counter::{Counter, ReadValue, XorValues};
+use rusk_vm::{Contract, GasMeter, HostModules, NetworkState};
+use stack::{Peek, PushMulti, Stack};
+
+const THREADS: usize = 16;
+const QUERIES_PER_THREAD: u64 = 64;
+const STACK_SIZE: u64 = 256;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn state_is_send_sync() {
+    assert_send_sync::<NetworkState>();
+    assert_send_sync::<HostModules>();
+}
+
+#[test]
+fn concurrent_queries() {
+    let mut network = NetworkState::new();
+
+    let counter_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+    let stack_code =
+        include_bytes!("../target/wasm32-unknown-unknown/release/stack.wasm");
+
+    let counter = Contract::new(
+        &Counter::new(99),
+        counter_code.to_vec(),
+        network.store(),
+    );
+    let counter_id = network.deploy(counter).expect("Deploy error");
+
+    let stack =
+        Contract::new(&Stack::new(), stack_code.to_vec(), network.store());
+    let stack_id = network.deploy(stack).expect("Deploy error");
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+    let (_, network) = network
+        .transact(stack_id, 0, PushMulti::new(STACK_SIZE), &mut gas)
+        .expect("Transaction should succeed");
+
+    let network = Arc::new(network);
+    let root = network.root();
+
+    let handles: Vec<_> = (0..THREADS)
+        .map(|t| {
+            let network = Arc::clone(&network);
+
+            thread::spawn(move || {
+                let mut gas = GasMeter::with_limit(1_000_000_000);
+
+                for i in 0..QUERIES_PER_THREAD {
+                    let value = network
+                        .query(counter_id, 0, ReadValue, &mut gas)
+                        .expect("Query should succeed");
+                    assert_eq!(*value, 99);
+
+                    let (a, b) = (t as i32, i as i32);
+                    let xored = network
+                        .query(counter_id, 0, XorValues::new(a, b), &mut gas)
+                        .expect("Query should succeed");
+                    assert_eq!(*xored, 99 ^ a ^ b);
+
+                    let n = (t as u64 * QUERIES_PER_THREAD + i) % STACK_SIZE;
+                    let peeked = network
+                        .query(stack_id, 0, Peek::new(n), &mut gas)
+                        .expect("Query should succeed");
+                    assert_eq!(*peeked, Some(n));
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().expect("Querying thread panicked");
+    }
+
+    // Queries leave the state untouched
+    assert_eq!(network.root(), root);
+}
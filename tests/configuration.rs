@@ -7,6 +7,7 @@
 use block_height::{BlockHeight, ReadBlockHeight};
 use counter::{Counter, ReadValue};
 use delegator::{Delegator, QueryForwardData};
+use hasher::{HashBytes, Hasher};
 use rusk_vm::{Config, Contract, GasMeter, HostCosts, NetworkState, OpCosts};
 use stack::{Push, Stack};
 
@@ -228,3 +229,49 @@ const NO_METERING_CONFIG: Config = Config {
 fn no_gas_consumption_when_metering_is_off() {
     assert_eq!(execute_counter_with_config(&NO_METERING_CONFIG), 0);
 }
+
+const HASHED_BYTES: usize = 1000;
+
+fn execute_hasher_with_config(config: &'static Config) -> u64 {
+    let mut network = NetworkState::builder().config(config).build();
+
+    let code =
+        include_bytes!("../target/wasm32-unknown-unknown/release/hasher.wasm");
+
+    let contract = Contract::new(&Hasher, code.to_vec(), network.store());
+    let contract_id = network.deploy(contract).expect("Deploy error");
+
+    let mut gas = GasMeter::with_limit(GAS_LIMIT);
+
+    network
+        .query(
+            contract_id,
+            0,
+            HashBytes::new([0xab; HASHED_BYTES]),
+            &mut gas,
+        )
+        .expect("Query error");
+
+    gas.spent()
+}
+
+const HIGH_HASH_COST: Config = Config {
+    host_costs: HostCosts {
+        hash_per_byte: 11,
+        ..HostCosts::new()
+    },
+    ..Config::new()
+};
+
+#[test]
+fn change_gas_cost_per_hashed_byte() {
+    let cheap = execute_hasher_with_config(&DEFAULT_CONFIG);
+    let expensive = execute_hasher_with_config(&HIGH_HASH_COST);
+
+    assert_eq!(
+        expensive - cheap,
+        HASHED_BYTES as u64
+            * (HIGH_HASH_COST.host_costs.hash_per_byte
+                - DEFAULT_CONFIG.host_costs.hash_per_byte)
+    );
+}
@@ -0,0 +1,123 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![no_std]
+#![feature(core_intrinsics, lang_items, alloc_error_handler)]
+
+use rkyv::{Archive, Deserialize, Serialize};
+use rusk_uplink::hosted::external;
+use rusk_uplink::{Apply, StoreContext, Transaction};
+use rusk_uplink_derive::{apply, init, state, transaction};
+
+/// Offset past the end of the contract's memory, and of the store.
+pub const OUT_OF_BOUNDS: u32 = i32::MAX as u32;
+
+/// The host functions taking offsets into the contract's memory or the store,
+/// in the order they are selected by [`Attack`].
+pub const HOST_FUNCTIONS: [&str; 21] = [
+    "sig",
+    "debug",
+    "query",
+    "transact",
+    "emit",
+    "deploy",
+    "upgrade",
+    "callee",
+    "caller",
+    "_get",
+    "_put",
+    "hash",
+    "storage_get",
+    "storage_set",
+    "storage_remove",
+    "sha256",
+    "keccak256",
+    "poseidon_hash",
+    "schnorr_verify",
+    "bls_verify",
+    "verify_proof",
+];
+
+extern "C" {
+    fn sig(msg: &u8, len: i32);
+    fn _put(slice: &u8, len: u16) -> u64;
+    fn _get(offset: u64, len: u16, buf: &mut u8);
+}
+
+#[state]
+pub struct Adversarial;
+#[init]
+fn init() {}
+
+/// Calls one of the [`HOST_FUNCTIONS`] with an offset out of the bounds of
+/// the contract's memory.
+#[transaction]
+pub struct Attack {
+    host_fn: u8,
+}
+
+impl Transaction for Attack {
+    const NAME: &'static str = "attack";
+    type Return = ();
+}
+
+#[apply(name = "attack")]
+impl Apply<Attack> for Adversarial {
+    fn apply(&mut self, attack: Attack, _: StoreContext) {
+        let mut buf = [0u8; 32];
+        let mut id = [0u8; 32];
+
+        unsafe {
+            let oob = &*(OUT_OF_BOUNDS as usize as *const u8);
+            let oob_mut = &mut *(OUT_OF_BOUNDS as usize as *mut u8);
+
+            match HOST_FUNCTIONS[attack.host_fn as usize] {
+                "sig" => sig(oob, 1),
+                "debug" => external::debug(oob, 1),
+                "query" => {
+                    external::query(oob, &buf[0], 1, 32, &buf[0], 1, 0);
+                }
+                "transact" => {
+                    external::transact(oob, &buf[0], 1, 32, &buf[0], 1, 0);
+                }
+                "emit" => external::emit(oob, 1, &buf[0], 1, &buf[0], 0),
+                "deploy" => external::deploy(oob, 1, &buf[0], 1, &mut id[0]),
+                "upgrade" => external::upgrade(oob, 1),
+                "callee" => external::callee(oob_mut),
+                "caller" => external::caller(oob_mut),
+                "_get" => {
+                    _get(OUT_OF_BOUNDS as u64, u16::MAX, &mut buf[0]);
+                }
+                "_put" => {
+                    _put(oob, 1);
+                }
+                "hash" => external::hash(oob, 1, &mut buf[0]),
+                "storage_get" => {
+                    external::storage_get(oob, 1, &mut buf[0], 32);
+                }
+                "storage_set" => external::storage_set(oob, 1, &buf[0], 1),
+                "storage_remove" => {
+                    external::storage_remove(oob, 1);
+                }
+                "sha256" => external::sha256(oob, 1, &mut buf[0]),
+                "keccak256" => external::keccak256(oob, 1, &mut buf[0]),
+                "poseidon_hash" => {
+                    external::poseidon_hash(oob, 32, &mut buf[0]);
+                }
+                "schnorr_verify" => {
+                    external::schnorr_verify(oob, &buf[0], &id[0]);
+                }
+                "bls_verify" => {
+                    external::bls_verify(oob, &buf[0], &id[0], 1);
+                }
+                "verify_proof" => {
+                    external::verify_proof(oob, 1, &buf[0], 1, &buf[0], 0);
+                }
+                _ => (),
+            }
+        }
+    }
+}
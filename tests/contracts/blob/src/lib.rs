@@ -0,0 +1,66 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![no_std]
+#![feature(core_intrinsics, lang_items, alloc_error_handler)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use rkyv::{Archive, Deserialize, Serialize};
+use rusk_uplink::{Apply, Execute, Query, StoreContext, Transaction};
+use rusk_uplink_derive::{apply, execute, init, query, state, transaction};
+
+/// A contract keeping its bytes inline in its state, so that the state grows
+/// along with them.
+#[state(new = false)]
+pub struct Blob {
+    bytes: Vec<u8>,
+}
+#[init]
+fn init() {}
+
+impl Blob {
+    pub fn new() -> Self {
+        Blob { bytes: Vec::new() }
+    }
+}
+
+#[transaction]
+pub struct BlobAppend {
+    byte: u8,
+    len: u32,
+}
+
+impl Transaction for BlobAppend {
+    const NAME: &'static str = "append";
+    type Return = u32;
+}
+
+#[apply(name = "append")]
+impl Apply<BlobAppend> for Blob {
+    fn apply(&mut self, t: BlobAppend, _: StoreContext) -> u32 {
+        let len = self.bytes.len() + t.len as usize;
+        self.bytes.resize(len, t.byte);
+        len as u32
+    }
+}
+
+#[query]
+pub struct BlobRead;
+
+impl Query for BlobRead {
+    const NAME: &'static str = "read";
+    type Return = Vec<u8>;
+}
+
+#[execute(name = "read")]
+impl Execute<BlobRead> for Blob {
+    fn execute(&self, _: BlobRead, _: StoreContext) -> Vec<u8> {
+        self.bytes.clone()
+    }
+}
@@ -7,11 +7,13 @@
 #![no_std]
 #![feature(core_intrinsics, lang_items, alloc_error_handler)]
 
+use bytecheck::CheckBytes;
 use rkyv::{Archive, Deserialize, Serialize};
 use rusk_uplink::{Apply, Execute, Query, StoreContext, Transaction};
 use rusk_uplink_derive::{apply, execute, init, query, state, transaction};
 
 #[state]
+#[archive_attr(derive(CheckBytes))]
 pub struct Counter {
     #[new(value = "0xffffffff")]
     junk: u32,
@@ -84,6 +86,22 @@ impl Transaction for CompareAndSwap {
     type Return = bool;
 }
 
+#[transaction]
+pub struct SelfDestruct;
+
+impl Transaction for SelfDestruct {
+    const NAME: &'static str = "destruct";
+    type Return = ();
+}
+
+#[query]
+pub struct DestructInQuery;
+
+impl Query for DestructInQuery {
+    const NAME: &'static str = "destruct_in_query";
+    type Return = ();
+}
+
 #[apply(name = "adjust")]
 impl Apply<Adjust> for Counter {
     fn apply(&mut self, arg: Adjust, _: StoreContext) {
@@ -133,6 +151,20 @@ impl Apply<CompareAndSwap> for Counter {
     }
 }
 
+#[apply(name = "destruct")]
+impl Apply<SelfDestruct> for Counter {
+    fn apply(&mut self, _: SelfDestruct, _: StoreContext) {
+        rusk_uplink::self_destruct();
+    }
+}
+
+#[execute(name = "destruct_in_query")]
+impl Execute<DestructInQuery> for Counter {
+    fn execute(&self, _: DestructInQuery, _: StoreContext) {
+        rusk_uplink::self_destruct();
+    }
+}
+
 impl Counter {
     pub fn read_value(&self) -> i32 {
         self.value
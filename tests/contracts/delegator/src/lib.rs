@@ -10,8 +10,8 @@
 use microkelvin::{OffsetLen, StoreRef};
 use rkyv::{AlignedVec, Archive, Deserialize, Serialize};
 use rusk_uplink::{
-    Apply, ContractId, Execute, Query, RawQuery, RawTransaction, ReturnValue,
-    StoreContext, Transaction,
+    Apply, CallError, ContractId, Execute, Query, RawQuery, RawTransaction,
+    ReturnValue, StoreContext, Transaction,
 };
 use rusk_uplink_derive::{apply, execute, init, query, state, transaction};
 
@@ -79,6 +79,62 @@ impl Transaction for TransactionForwardData {
     type Return = ();
 }
 
+#[transaction(new = false)]
+pub struct TryTransactionForwardData {
+    contract_id: ContractId,
+    data: Box<[u8]>,
+    name: Box<str>,
+}
+
+impl TryTransactionForwardData {
+    pub fn new(
+        contract_id: ContractId,
+        data: impl AsRef<[u8]>,
+        name: impl AsRef<str>,
+    ) -> Self {
+        let data = Box::from(data.as_ref());
+        let name = Box::from(name.as_ref());
+        Self {
+            contract_id,
+            data,
+            name,
+        }
+    }
+}
+
+impl Transaction for TryTransactionForwardData {
+    const NAME: &'static str = "try_delegate_transaction";
+    type Return = Result<(), CallError>;
+}
+
+#[transaction(new = false)]
+pub struct TryQueryForwardData {
+    contract_id: ContractId,
+    data: Box<[u8]>,
+    name: Box<str>,
+}
+
+impl TryQueryForwardData {
+    pub fn new(
+        contract_id: ContractId,
+        data: impl AsRef<[u8]>,
+        name: impl AsRef<str>,
+    ) -> Self {
+        let data = Box::from(data.as_ref());
+        let name = Box::from(name.as_ref());
+        Self {
+            contract_id,
+            data,
+            name,
+        }
+    }
+}
+
+impl Transaction for TryQueryForwardData {
+    const NAME: &'static str = "try_delegate_query";
+    type Return = Result<(), CallError>;
+}
+
 #[execute(name = "delegate_query")]
 impl Execute<QueryForwardData> for Delegator {
     fn execute(&self, arg: QueryForwardData, mut store: StoreContext) -> u32 {
@@ -111,6 +167,46 @@ impl Apply<TransactionForwardData> for Delegator {
     }
 }
 
+#[apply(name = "try_delegate_transaction")]
+impl Apply<TryTransactionForwardData> for Delegator {
+    fn apply(
+        &mut self,
+        arg: TryTransactionForwardData,
+        store: StoreContext,
+    ) -> Result<(), CallError> {
+        let query_name = arg.name.as_ref();
+        let mut query_data = AlignedVec::new();
+        query_data.extend_from_slice(arg.data.as_ref());
+        rusk_uplink::transact_raw(
+            self,
+            &arg.contract_id,
+            &RawTransaction::from(query_data, query_name),
+            0,
+            store,
+        )
+        .map(|_| ())
+    }
+}
+
+#[apply(name = "try_delegate_query")]
+impl Apply<TryQueryForwardData> for Delegator {
+    fn apply(
+        &mut self,
+        arg: TryQueryForwardData,
+        _: StoreContext,
+    ) -> Result<(), CallError> {
+        let query_name = arg.name.as_ref();
+        let mut query_data = AlignedVec::new();
+        query_data.extend_from_slice(arg.data.as_ref());
+        rusk_uplink::query_raw(
+            &arg.contract_id,
+            &RawQuery::from(query_data, query_name),
+            0,
+        )
+        .map(|_| ())
+    }
+}
+
 impl Delegator {
     pub fn delegate_query(
         &self,
@@ -9,8 +9,11 @@
 
 use microkelvin::{OffsetLen, StoreRef};
 use rkyv::{Archive, Deserialize, Serialize};
-use rusk_uplink::{Execute, Query};
-use rusk_uplink_derive::{execute, init, query, state};
+use rusk_uplink::{Apply, Execute, Query, StoreContext, Transaction};
+use rusk_uplink_derive::{apply, execute, init, query, state, transaction};
+
+extern crate alloc;
+use alloc::vec::Vec;
 
 #[state]
 pub struct Events;
@@ -43,3 +46,50 @@ impl Execute<EventNum> for Events {
         rusk_uplink::emit("event_log", event_num.0, store);
     }
 }
+
+/// Emits `value` indexed by `topics`, followed by the same value unindexed.
+#[query]
+pub struct IndexedEvent {
+    topics: Vec<[u8; 32]>,
+    value: u32,
+}
+
+impl Query for IndexedEvent {
+    const NAME: &'static str = "indexed_event";
+    type Return = ();
+}
+
+#[execute(name = "indexed_event")]
+impl Execute<IndexedEvent> for Events {
+    fn execute(&self, arg: IndexedEvent, store: StoreRef<OffsetLen>) {
+        rusk_uplink::emit_indexed(
+            "indexed_log",
+            &arg.topics,
+            arg.value,
+            store.clone(),
+        );
+        rusk_uplink::emit("event_log", arg.value, store);
+    }
+}
+
+/// Emits `value`, panicking afterwards if `fail` is set.
+#[transaction]
+pub struct EmitTransaction {
+    value: u32,
+    fail: bool,
+}
+
+impl Transaction for EmitTransaction {
+    const NAME: &'static str = "emit_transaction";
+    type Return = ();
+}
+
+#[apply(name = "emit_transaction")]
+impl Apply<EmitTransaction> for Events {
+    fn apply(&mut self, arg: EmitTransaction, store: StoreContext) {
+        rusk_uplink::emit("event_log", arg.value, store);
+        if arg.fail {
+            panic!("Failed after emitting");
+        }
+    }
+}
@@ -0,0 +1,125 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![no_std]
+#![feature(core_intrinsics, lang_items, alloc_error_handler)]
+
+use rkyv::{Archive, Deserialize, Serialize};
+use rusk_uplink::{
+    Apply, ContractId, Execute, Query, StoreContext, Transaction,
+};
+use rusk_uplink_derive::{apply, execute, init, query, state, transaction};
+
+extern crate alloc;
+use alloc::boxed::Box;
+
+#[state]
+pub struct Factory;
+#[init]
+fn init() {}
+
+#[transaction(new = false)]
+pub struct Deploy {
+    code: Box<[u8]>,
+    state: Box<[u8]>,
+}
+
+impl Deploy {
+    pub fn new(code: impl AsRef<[u8]>, state: impl AsRef<[u8]>) -> Self {
+        let code = Box::from(code.as_ref());
+        let state = Box::from(state.as_ref());
+        Self { code, state }
+    }
+}
+
+impl Transaction for Deploy {
+    const NAME: &'static str = "deploy_contract";
+    type Return = ContractId;
+}
+
+#[apply(name = "deploy_contract")]
+impl Apply<Deploy> for Factory {
+    fn apply(&mut self, arg: Deploy, _: StoreContext) -> ContractId {
+        rusk_uplink::deploy_raw(&arg.code, &arg.state)
+    }
+}
+
+#[transaction(new = false)]
+pub struct DeployAndPanic {
+    code: Box<[u8]>,
+    state: Box<[u8]>,
+}
+
+impl DeployAndPanic {
+    pub fn new(code: impl AsRef<[u8]>, state: impl AsRef<[u8]>) -> Self {
+        let code = Box::from(code.as_ref());
+        let state = Box::from(state.as_ref());
+        Self { code, state }
+    }
+}
+
+impl Transaction for DeployAndPanic {
+    const NAME: &'static str = "deploy_and_panic";
+    type Return = ();
+}
+
+#[apply(name = "deploy_and_panic")]
+impl Apply<DeployAndPanic> for Factory {
+    fn apply(&mut self, arg: DeployAndPanic, _: StoreContext) {
+        rusk_uplink::deploy_raw(&arg.code, &arg.state);
+        panic!("Deployments are reverted")
+    }
+}
+
+#[query(new = false)]
+pub struct DeployInQuery {
+    code: Box<[u8]>,
+    state: Box<[u8]>,
+}
+
+impl DeployInQuery {
+    pub fn new(code: impl AsRef<[u8]>, state: impl AsRef<[u8]>) -> Self {
+        let code = Box::from(code.as_ref());
+        let state = Box::from(state.as_ref());
+        Self { code, state }
+    }
+}
+
+impl Query for DeployInQuery {
+    const NAME: &'static str = "deploy_in_query";
+    type Return = ContractId;
+}
+
+#[execute(name = "deploy_in_query")]
+impl Execute<DeployInQuery> for Factory {
+    fn execute(&self, arg: DeployInQuery, _: StoreContext) -> ContractId {
+        rusk_uplink::deploy_raw(&arg.code, &arg.state)
+    }
+}
+
+#[transaction(new = false)]
+pub struct Upgrade {
+    code: Box<[u8]>,
+}
+
+impl Upgrade {
+    pub fn new(code: impl AsRef<[u8]>) -> Self {
+        let code = Box::from(code.as_ref());
+        Self { code }
+    }
+}
+
+impl Transaction for Upgrade {
+    const NAME: &'static str = "upgrade_contract";
+    type Return = ();
+}
+
+#[apply(name = "upgrade_contract")]
+impl Apply<Upgrade> for Factory {
+    fn apply(&mut self, arg: Upgrade, _: StoreContext) {
+        rusk_uplink::upgrade(&arg.code)
+    }
+}
@@ -0,0 +1,54 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![no_std]
+#![feature(core_intrinsics, lang_items, alloc_error_handler)]
+
+use blake2b_simd::Params;
+use rkyv::{Archive, Deserialize, Serialize};
+use rusk_uplink::{Execute, Query, StoreContext};
+use rusk_uplink_derive::{execute, init, query, state};
+
+extern crate alloc;
+use alloc::boxed::Box;
+
+#[state]
+pub struct Hasher;
+#[init]
+fn init() {}
+
+/// Hashes the given bytes both with the `hash` host function and within the
+/// contract, returning both hashes.
+#[query(new = false)]
+pub struct HashBytes {
+    bytes: Box<[u8]>,
+}
+
+impl HashBytes {
+    pub fn new(bytes: impl AsRef<[u8]>) -> Self {
+        let bytes = Box::from(bytes.as_ref());
+        Self { bytes }
+    }
+}
+
+impl Query for HashBytes {
+    const NAME: &'static str = "hash_bytes";
+    type Return = ([u8; 32], [u8; 32]);
+}
+
+#[execute(name = "hash_bytes")]
+impl Execute<HashBytes> for Hasher {
+    fn execute(&self, arg: HashBytes, _: StoreContext) -> ([u8; 32], [u8; 32]) {
+        let host = rusk_uplink::hash(&arg.bytes);
+
+        let mut guest = [0u8; 32];
+        guest.copy_from_slice(
+            Params::new().hash_length(32).hash(&arg.bytes).as_bytes(),
+        );
+
+        (host, guest)
+    }
+}
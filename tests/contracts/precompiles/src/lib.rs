@@ -0,0 +1,161 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![no_std]
+#![feature(core_intrinsics, lang_items, alloc_error_handler)]
+
+use core::convert::TryInto;
+
+use rkyv::{Archive, Deserialize, Serialize};
+use rusk_uplink::{Execute, Query, StoreContext};
+use rusk_uplink_derive::{execute, init, query, state};
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+#[state]
+pub struct Precompiles;
+#[init]
+fn init() {}
+
+#[query(new = false)]
+pub struct Sha256 {
+    bytes: Box<[u8]>,
+}
+
+impl Sha256 {
+    pub fn new(bytes: impl AsRef<[u8]>) -> Self {
+        let bytes = Box::from(bytes.as_ref());
+        Self { bytes }
+    }
+}
+
+impl Query for Sha256 {
+    const NAME: &'static str = "hash_sha256";
+    type Return = [u8; 32];
+}
+
+#[execute(name = "hash_sha256")]
+impl Execute<Sha256> for Precompiles {
+    fn execute(&self, arg: Sha256, _: StoreContext) -> [u8; 32] {
+        rusk_uplink::sha256(&arg.bytes)
+    }
+}
+
+#[query(new = false)]
+pub struct Keccak256 {
+    bytes: Box<[u8]>,
+}
+
+impl Keccak256 {
+    pub fn new(bytes: impl AsRef<[u8]>) -> Self {
+        let bytes = Box::from(bytes.as_ref());
+        Self { bytes }
+    }
+}
+
+impl Query for Keccak256 {
+    const NAME: &'static str = "hash_keccak256";
+    type Return = [u8; 32];
+}
+
+#[execute(name = "hash_keccak256")]
+impl Execute<Keccak256> for Precompiles {
+    fn execute(&self, arg: Keccak256, _: StoreContext) -> [u8; 32] {
+        rusk_uplink::keccak256(&arg.bytes)
+    }
+}
+
+#[query]
+pub struct PoseidonHash {
+    scalars: Vec<[u8; 32]>,
+}
+
+impl Query for PoseidonHash {
+    const NAME: &'static str = "hash_poseidon";
+    type Return = Option<[u8; 32]>;
+}
+
+#[execute(name = "hash_poseidon")]
+impl Execute<PoseidonHash> for Precompiles {
+    fn execute(&self, arg: PoseidonHash, _: StoreContext) -> Option<[u8; 32]> {
+        rusk_uplink::poseidon_hash(&arg.scalars)
+    }
+}
+
+/// Verifies a Schnorr signature. Keys and signatures of the wrong length are
+/// considered invalid.
+#[query(new = false)]
+pub struct SchnorrVerify {
+    pk: Box<[u8]>,
+    msg: [u8; 32],
+    sig: Box<[u8]>,
+}
+
+impl SchnorrVerify {
+    pub fn new(
+        pk: impl AsRef<[u8]>,
+        msg: [u8; 32],
+        sig: impl AsRef<[u8]>,
+    ) -> Self {
+        let pk = Box::from(pk.as_ref());
+        let sig = Box::from(sig.as_ref());
+        Self { pk, msg, sig }
+    }
+}
+
+impl Query for SchnorrVerify {
+    const NAME: &'static str = "verify_schnorr";
+    type Return = bool;
+}
+
+#[execute(name = "verify_schnorr")]
+impl Execute<SchnorrVerify> for Precompiles {
+    fn execute(&self, arg: SchnorrVerify, _: StoreContext) -> bool {
+        match (arg.pk.as_ref().try_into(), arg.sig.as_ref().try_into()) {
+            (Ok(pk), Ok(sig)) => rusk_uplink::schnorr_verify(pk, &arg.msg, sig),
+            _ => false,
+        }
+    }
+}
+
+/// Verifies a BLS signature. Keys and signatures of the wrong length are
+/// considered invalid.
+#[query(new = false)]
+pub struct BlsVerify {
+    pk: Box<[u8]>,
+    sig: Box<[u8]>,
+    msg: Box<[u8]>,
+}
+
+impl BlsVerify {
+    pub fn new(
+        pk: impl AsRef<[u8]>,
+        sig: impl AsRef<[u8]>,
+        msg: impl AsRef<[u8]>,
+    ) -> Self {
+        let pk = Box::from(pk.as_ref());
+        let sig = Box::from(sig.as_ref());
+        let msg = Box::from(msg.as_ref());
+        Self { pk, sig, msg }
+    }
+}
+
+impl Query for BlsVerify {
+    const NAME: &'static str = "verify_bls";
+    type Return = bool;
+}
+
+#[execute(name = "verify_bls")]
+impl Execute<BlsVerify> for Precompiles {
+    fn execute(&self, arg: BlsVerify, _: StoreContext) -> bool {
+        match (arg.pk.as_ref().try_into(), arg.sig.as_ref().try_into()) {
+            (Ok(pk), Ok(sig)) => rusk_uplink::bls_verify(pk, sig, &arg.msg),
+            _ => false,
+        }
+    }
+}
@@ -7,6 +7,7 @@
 #![no_std]
 #![feature(core_intrinsics, lang_items, alloc_error_handler)]
 
+use bytecheck::CheckBytes;
 use microkelvin::{All, Cardinality, Compound, Nth, OffsetLen};
 use nstack::NStack;
 use rkyv::{Archive, Deserialize, Serialize};
@@ -14,6 +15,7 @@ use rusk_uplink::{Apply, Execute, Query, StoreContext, Transaction};
 use rusk_uplink_derive::{apply, execute, init, query, state, transaction};
 
 #[state(new = false)]
+#[archive_attr(derive(CheckBytes))]
 pub struct Stack {
     inner: NStack<u64, Cardinality, OffsetLen>,
 }
@@ -0,0 +1,148 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![no_std]
+#![feature(core_intrinsics, lang_items, alloc_error_handler)]
+
+use rkyv::{Archive, Deserialize, Serialize};
+use rusk_uplink::{Apply, Execute, Query, StoreContext, Transaction};
+use rusk_uplink_derive::{apply, execute, init, query, state, transaction};
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+#[state]
+pub struct KeyValue;
+#[init]
+fn init() {}
+
+#[query(new = false)]
+pub struct Get {
+    key: Box<[u8]>,
+}
+
+impl Get {
+    pub fn new(key: impl AsRef<[u8]>) -> Self {
+        let key = Box::from(key.as_ref());
+        Self { key }
+    }
+}
+
+impl Query for Get {
+    const NAME: &'static str = "get";
+    type Return = Option<Vec<u8>>;
+}
+
+#[execute(name = "get")]
+impl Execute<Get> for KeyValue {
+    fn execute(&self, arg: Get, _: StoreContext) -> Option<Vec<u8>> {
+        rusk_uplink::storage_get(&arg.key)
+    }
+}
+
+#[transaction(new = false)]
+pub struct Set {
+    key: Box<[u8]>,
+    value: Box<[u8]>,
+}
+
+impl Set {
+    pub fn new(key: impl AsRef<[u8]>, value: impl AsRef<[u8]>) -> Self {
+        let key = Box::from(key.as_ref());
+        let value = Box::from(value.as_ref());
+        Self { key, value }
+    }
+}
+
+impl Transaction for Set {
+    const NAME: &'static str = "set";
+    type Return = ();
+}
+
+#[apply(name = "set")]
+impl Apply<Set> for KeyValue {
+    fn apply(&mut self, arg: Set, _: StoreContext) {
+        rusk_uplink::storage_set(&arg.key, &arg.value)
+    }
+}
+
+#[query(new = false)]
+pub struct SetInQuery {
+    key: Box<[u8]>,
+    value: Box<[u8]>,
+}
+
+impl SetInQuery {
+    pub fn new(key: impl AsRef<[u8]>, value: impl AsRef<[u8]>) -> Self {
+        let key = Box::from(key.as_ref());
+        let value = Box::from(value.as_ref());
+        Self { key, value }
+    }
+}
+
+impl Query for SetInQuery {
+    const NAME: &'static str = "set_in_query";
+    type Return = ();
+}
+
+#[execute(name = "set_in_query")]
+impl Execute<SetInQuery> for KeyValue {
+    fn execute(&self, arg: SetInQuery, _: StoreContext) {
+        rusk_uplink::storage_set(&arg.key, &arg.value)
+    }
+}
+
+#[transaction(new = false)]
+pub struct SetAndPanic {
+    key: Box<[u8]>,
+    value: Box<[u8]>,
+}
+
+impl SetAndPanic {
+    pub fn new(key: impl AsRef<[u8]>, value: impl AsRef<[u8]>) -> Self {
+        let key = Box::from(key.as_ref());
+        let value = Box::from(value.as_ref());
+        Self { key, value }
+    }
+}
+
+impl Transaction for SetAndPanic {
+    const NAME: &'static str = "set_and_panic";
+    type Return = ();
+}
+
+#[apply(name = "set_and_panic")]
+impl Apply<SetAndPanic> for KeyValue {
+    fn apply(&mut self, arg: SetAndPanic, _: StoreContext) {
+        rusk_uplink::storage_set(&arg.key, &arg.value);
+        panic!("Storage writes are reverted")
+    }
+}
+
+#[transaction(new = false)]
+pub struct Remove {
+    key: Box<[u8]>,
+}
+
+impl Remove {
+    pub fn new(key: impl AsRef<[u8]>) -> Self {
+        let key = Box::from(key.as_ref());
+        Self { key }
+    }
+}
+
+impl Transaction for Remove {
+    const NAME: &'static str = "remove";
+    type Return = bool;
+}
+
+#[apply(name = "remove")]
+impl Apply<Remove> for KeyValue {
+    fn apply(&mut self, arg: Remove, _: StoreContext) -> bool {
+        rusk_uplink::storage_remove(&arg.key)
+    }
+}
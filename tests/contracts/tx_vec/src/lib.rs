@@ -16,6 +16,7 @@ use rusk_uplink_derive::{apply, execute, init, query, state, transaction};
 
 extern crate alloc;
 use alloc::boxed::Box;
+use alloc::vec;
 
 #[state]
 pub struct TxVec {
@@ -114,3 +115,149 @@ impl Apply<TxVecDelegateSum> for TxVec {
         self.delegate_sum(&s.contract_id, &s.data, store)
     }
 }
+
+#[query]
+pub struct TxVecRepeat {
+    len: u32,
+}
+
+impl Query for TxVecRepeat {
+    const NAME: &'static str = "repeat";
+    type Return = Box<[u8]>;
+}
+
+#[execute(name = "repeat")]
+impl Execute<TxVecRepeat> for TxVec {
+    fn execute(&self, q: TxVecRepeat, _: StoreContext) -> Box<[u8]> {
+        vec![self.value; q.len as usize].into_boxed_slice()
+    }
+}
+
+#[query]
+pub struct TxVecDelegateRepeat {
+    contract_id: ContractId,
+    len: u32,
+}
+
+impl Query for TxVecDelegateRepeat {
+    const NAME: &'static str = "delegate_repeat";
+    type Return = Box<[u8]>;
+}
+
+#[execute(name = "delegate_repeat")]
+impl Execute<TxVecDelegateRepeat> for TxVec {
+    fn execute(
+        &self,
+        q: TxVecDelegateRepeat,
+        store: StoreContext,
+    ) -> Box<[u8]> {
+        rusk_uplink::query(&q.contract_id, TxVecRepeat::new(q.len), 0, store)
+            .unwrap()
+    }
+}
+
+#[transaction(new = false)]
+pub struct TxVecEcho {
+    values: Box<[u8]>,
+}
+
+impl TxVecEcho {
+    pub fn new(v: impl AsRef<[u8]>) -> Self {
+        Self {
+            values: Box::from(v.as_ref()),
+        }
+    }
+}
+
+impl Transaction for TxVecEcho {
+    const NAME: &'static str = "echo";
+    type Return = Box<[u8]>;
+}
+
+#[apply(name = "echo")]
+impl Apply<TxVecEcho> for TxVec {
+    fn apply(&mut self, s: TxVecEcho, _: StoreContext) -> Box<[u8]> {
+        self.sum(&s.values);
+        s.values
+    }
+}
+
+#[transaction(new = false)]
+pub struct TxVecDelegateEcho {
+    contract_id: ContractId,
+    data: Box<[u8]>,
+}
+
+impl TxVecDelegateEcho {
+    pub fn new(contract_id: ContractId, data: impl AsRef<[u8]>) -> Self {
+        let data = Box::from(data.as_ref());
+        Self { contract_id, data }
+    }
+}
+
+impl Transaction for TxVecDelegateEcho {
+    const NAME: &'static str = "delegate_echo";
+    type Return = Box<[u8]>;
+}
+
+#[apply(name = "delegate_echo")]
+impl Apply<TxVecDelegateEcho> for TxVec {
+    fn apply(
+        &mut self,
+        s: TxVecDelegateEcho,
+        store: StoreContext,
+    ) -> Box<[u8]> {
+        rusk_uplink::transact(
+            self,
+            &s.contract_id,
+            TxVecEcho::new(&s.data),
+            0,
+            store,
+        )
+        .unwrap()
+    }
+}
+
+#[transaction(new = false)]
+pub struct TxVecDelegateRaw {
+    contract_id: ContractId,
+    data: Box<[u8]>,
+    name: Box<str>,
+}
+
+impl TxVecDelegateRaw {
+    pub fn new(
+        contract_id: ContractId,
+        data: impl AsRef<[u8]>,
+        name: impl AsRef<str>,
+    ) -> Self {
+        let data = Box::from(data.as_ref());
+        let name = Box::from(name.as_ref());
+        Self {
+            contract_id,
+            data,
+            name,
+        }
+    }
+}
+
+impl Transaction for TxVecDelegateRaw {
+    const NAME: &'static str = "delegate_raw";
+    type Return = Box<[u8]>;
+}
+
+#[apply(name = "delegate_raw")]
+impl Apply<TxVecDelegateRaw> for TxVec {
+    fn apply(&mut self, s: TxVecDelegateRaw, store: StoreContext) -> Box<[u8]> {
+        let raw_transaction = RawTransaction::from(s.data.to_vec(), &s.name);
+        let ret = rusk_uplink::transact_raw(
+            self,
+            &s.contract_id,
+            &raw_transaction,
+            0,
+            store,
+        )
+        .unwrap();
+        Box::from(ret.data())
+    }
+}
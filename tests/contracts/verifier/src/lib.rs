@@ -0,0 +1,60 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![no_std]
+#![feature(core_intrinsics, lang_items, alloc_error_handler)]
+
+use rkyv::{Archive, Deserialize, Serialize};
+use rusk_uplink::{Execute, Query, StoreContext};
+use rusk_uplink_derive::{execute, init, query, state};
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+#[state]
+pub struct Verifier;
+#[init]
+fn init() {}
+
+#[query(new = false)]
+pub struct VerifyProof {
+    verifier_data: Box<[u8]>,
+    proof: Box<[u8]>,
+    public_inputs: Vec<[u8; 32]>,
+}
+
+impl VerifyProof {
+    pub fn new(
+        verifier_data: impl AsRef<[u8]>,
+        proof: impl AsRef<[u8]>,
+        public_inputs: Vec<[u8; 32]>,
+    ) -> Self {
+        let verifier_data = Box::from(verifier_data.as_ref());
+        let proof = Box::from(proof.as_ref());
+        Self {
+            verifier_data,
+            proof,
+            public_inputs,
+        }
+    }
+}
+
+impl Query for VerifyProof {
+    const NAME: &'static str = "verify";
+    type Return = bool;
+}
+
+#[execute(name = "verify")]
+impl Execute<VerifyProof> for Verifier {
+    fn execute(&self, arg: VerifyProof, _: StoreContext) -> bool {
+        rusk_uplink::verify_proof(
+            &arg.verifier_data,
+            &arg.proof,
+            &arg.public_inputs,
+        )
+    }
+}
@@ -0,0 +1,172 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use bytecheck::CheckBytes;
+use delegator::{Delegator, QueryForwardData, TransactionForwardData};
+use rkyv::ser::serializers::BufferSerializer;
+use rkyv::ser::Serializer;
+use rkyv::{check_archived_root, Archive, Deserialize, Serialize};
+use rusk_uplink::{Query, RawQuery, ReturnValue, StoreContext};
+use rusk_vm::{
+    Contract, ContractId, GasMeter, HostModule, NetworkState, VMError,
+};
+
+const DOUBLER_ID: ContractId = ContractId::reserved(0x40);
+const DOUBLER_COST: u64 = 1_000;
+
+#[derive(Clone, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+struct Double(u32);
+
+impl Query for Double {
+    const NAME: &'static str = "double";
+    type Return = u32;
+}
+
+/// A host module doubling the number it is given.
+struct Doubler;
+
+impl HostModule for Doubler {
+    fn module_id(&self) -> ContractId {
+        DOUBLER_ID
+    }
+
+    fn execute(
+        &self,
+        query: RawQuery,
+        _caller: ContractId,
+        store: &StoreContext,
+        gas_meter: &mut GasMeter,
+    ) -> Result<ReturnValue, VMError> {
+        gas_meter.charge(DOUBLER_COST)?;
+
+        match query.name() {
+            Double::NAME => {
+                let double = check_archived_root::<Double>(query.data())
+                    .map_err(|_| VMError::InvalidData)?;
+
+                let mut ser = store.serializer();
+                ser.serialize_value(&(double.0 * 2)).unwrap();
+
+                Ok(ReturnValue::new(ser.spill_bytes(|bytes| Vec::from(bytes))))
+            }
+            _ => Err(VMError::InvalidData),
+        }
+    }
+}
+
+fn serialize_double(value: u32, buf: &mut [u8]) -> usize {
+    let mut ser = BufferSerializer::new(buf);
+    ser.serialize_value(&Double(value)).unwrap()
+        + core::mem::size_of::<<Double as Archive>::Archived>()
+}
+
+#[test]
+fn host_module_query() {
+    let network = NetworkState::builder().module(Doubler).build();
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    assert_eq!(
+        *network
+            .query(DOUBLER_ID, 0, Double(21), &mut gas)
+            .expect("Query to a host module should succeed"),
+        42
+    );
+    assert_eq!(gas.spent(), DOUBLER_COST);
+}
+
+#[test]
+fn host_module_out_of_gas() {
+    let network = NetworkState::builder().module(Doubler).build();
+
+    let mut gas = GasMeter::with_limit(DOUBLER_COST - 1);
+
+    let result = network.query(DOUBLER_ID, 0, Double(21), &mut gas);
+    assert!(matches!(result, Err(VMError::OutOfGas)));
+    assert_eq!(gas.left(), 0);
+}
+
+#[test]
+fn host_module_from_contract() {
+    let mut network = NetworkState::builder().module(Doubler).build();
+
+    let delegator_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/delegator.wasm"
+    );
+    let delegator_contract =
+        Contract::new(&Delegator, delegator_code.to_vec(), network.store());
+    let delegator_id = network.deploy(delegator_contract).unwrap();
+
+    let mut buf = [0u8; 128];
+    let len = serialize_double(21, &mut buf);
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    assert_eq!(
+        *network
+            .query(
+                delegator_id,
+                0,
+                QueryForwardData::new(DOUBLER_ID, &buf[..len], Double::NAME),
+                &mut gas,
+            )
+            .expect("Delegated query to a host module should succeed"),
+        42
+    );
+    assert!(gas.spent() > DOUBLER_COST);
+
+    network
+        .transact(
+            delegator_id,
+            0,
+            TransactionForwardData::new(DOUBLER_ID, &buf[..len], Double::NAME),
+            &mut gas,
+        )
+        .expect("Delegated transaction to a host module should succeed");
+}
+
+#[test]
+fn host_module_from_stateful_contract() {
+    use rkyv::AlignedVec;
+    use tx_vec::{TxVec, TxVecDelegateRaw, TxVecReadValue};
+
+    let mut network = NetworkState::builder().module(Doubler).build();
+
+    let code =
+        include_bytes!("../target/wasm32-unknown-unknown/release/tx_vec.wasm");
+    let contract =
+        Contract::new(&TxVec::new(7), code.to_vec(), network.store());
+    let contract_id = network.deploy(contract).unwrap();
+
+    let mut buf = [0u8; 128];
+    let len = serialize_double(21, &mut buf);
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    let (receipt, network) = network
+        .transact(
+            contract_id,
+            0,
+            TxVecDelegateRaw::new(DOUBLER_ID, &buf[..len], Double::NAME),
+            &mut gas,
+        )
+        .expect("Transaction to a host module should succeed");
+
+    let mut result = AlignedVec::new();
+    result.extend_from_slice(&receipt);
+    let doubled = check_archived_root::<u32>(&result)
+        .expect("The module should return a number");
+    assert_eq!(*doubled, 42);
+
+    // The module returns no state, leaving the caller's as it was
+    assert_eq!(
+        *network
+            .query(contract_id, 0, TxVecReadValue, &mut gas)
+            .unwrap(),
+        7
+    );
+}
@@ -4,6 +4,7 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use blob::{Blob, BlobAppend, BlobRead};
 use block_height::{BlockHeight, ReadBlockHeight};
 use callee_1::{Callee1State, Callee1Transaction};
 use callee_2::Callee2State;
@@ -16,7 +17,10 @@ use map::{Get, Map, Remove, Set};
 use register::{Gossip, NumSecrets, Register, SecretHash};
 use rusk_vm::{Contract, GasMeter, NetworkState};
 use self_snapshot::SelfSnapshot;
-use tx_vec::{TxVec, TxVecDelegateSum, TxVecReadValue, TxVecSum};
+use tx_vec::{
+    TxVec, TxVecDelegateEcho, TxVecDelegateRepeat, TxVecDelegateSum,
+    TxVecReadValue, TxVecSum,
+};
 
 mod dual_test;
 
@@ -161,11 +165,151 @@ fn events() {
     let receipt = network
         .query(contract_id, 0, EventNum(10), &mut gas)
         .unwrap();
+    assert_eq!(receipt.events().len(), 11);
     for (i, event) in receipt.events().iter().enumerate() {
         assert_eq!(&(i as i32).to_le_bytes()[..], event.data());
+        assert_eq!(*event.cast::<u32>().unwrap(), i as u32);
+
+        // The innermost call emits first
+        assert_eq!(event.depth(), 10 - i);
+        assert_eq!(event.frame(), 10 - i);
     }
 }
 
+#[test]
+fn indexed_events() {
+    use events::*;
+    use rusk_vm::{Bloom, ContractId};
+
+    let mut network = NetworkState::new();
+
+    let code =
+        include_bytes!("../target/wasm32-unknown-unknown/release/events.wasm");
+
+    let contract = Contract::new(&Events, code.to_vec(), network.store());
+    let contract_id = network.deploy(contract).unwrap();
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    let topics = vec![[1u8; 32], [2u8; 32]];
+    let receipt = network
+        .query(
+            contract_id,
+            0,
+            IndexedEvent::new(topics.clone(), 42),
+            &mut gas,
+        )
+        .unwrap();
+
+    let indexed: Vec<_> =
+        receipt.events_by(&contract_id, "indexed_log").collect();
+    assert_eq!(indexed.len(), 1);
+    assert_eq!(indexed[0].topics(), &topics[..]);
+    assert_eq!(*indexed[0].cast::<u32>().unwrap(), 42);
+    assert_eq!(indexed[0].depth(), 0);
+
+    let unindexed: Vec<_> =
+        receipt.events_by(&contract_id, "event_log").collect();
+    assert_eq!(unindexed.len(), 1);
+    assert!(unindexed[0].topics().is_empty());
+
+    let other = ContractId::reserved(0x42);
+    assert_eq!(receipt.events_by(&other, "indexed_log").count(), 0);
+    assert_eq!(receipt.events_by(&contract_id, "other_log").count(), 0);
+
+    let bloom = receipt.bloom();
+    assert!(bloom.contains_origin(&contract_id));
+    assert!(bloom.contains_event(&contract_id, "indexed_log"));
+    assert!(bloom.contains_event(&contract_id, "event_log"));
+    assert!(bloom.contains_topic(&[1u8; 32]));
+    assert!(bloom.contains_topic(&[2u8; 32]));
+    assert!(!bloom.contains_origin(&other));
+    assert!(!bloom.contains_event(&other, "indexed_log"));
+    assert!(!bloom.contains_topic(&[3u8; 32]));
+
+    // A block's bloom is the union of the blooms of its receipts
+    let receipt = network
+        .query(
+            contract_id,
+            0,
+            IndexedEvent::new(vec![[3u8; 32]], 7),
+            &mut gas,
+        )
+        .unwrap();
+
+    let mut block = Bloom::new();
+    block.union(bloom);
+    assert!(!block.contains_topic(&[3u8; 32]));
+    block.union(receipt.bloom());
+    assert!(block.contains_topic(&[1u8; 32]));
+    assert!(block.contains_topic(&[3u8; 32]));
+}
+
+#[test]
+fn failed_call_events() {
+    use delegator::TryTransactionForwardData;
+    use events::*;
+    use rkyv::ser::serializers::BufferSerializer;
+    use rkyv::ser::Serializer;
+    use rkyv::Archive;
+    use rusk_uplink::CallError;
+
+    let mut network = NetworkState::new();
+
+    let code =
+        include_bytes!("../target/wasm32-unknown-unknown/release/events.wasm");
+    let delegator_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/delegator.wasm"
+    );
+
+    let contract = Contract::new(&Events, code.to_vec(), network.store());
+    let delegator_contract =
+        Contract::new(&Delegator, delegator_code.to_vec(), network.store());
+
+    let contract_id = network.deploy(contract).unwrap();
+    let delegator_id = network.deploy(delegator_contract).unwrap();
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    let forward = |value: u32, fail: bool| {
+        let mut buf = [0u8; 128];
+        let mut ser = BufferSerializer::new(&mut buf);
+        let buffer_len = ser
+            .serialize_value(&EmitTransaction::new(value, fail))
+            .unwrap()
+            + core::mem::size_of::<<EmitTransaction as Archive>::Archived>();
+
+        TryTransactionForwardData::new(
+            contract_id,
+            &buf[..buffer_len],
+            "emit_transaction",
+        )
+    };
+
+    // the events of a successful nested call are kept
+    let (receipt, network) = network
+        .transact(delegator_id, 0, forward(7, false), &mut gas)
+        .expect("The delegator should succeed");
+
+    assert_eq!(*receipt, Ok(()));
+    assert_eq!(receipt.events().len(), 1);
+
+    let event = &receipt.events()[0];
+    assert_eq!(event.origin(), contract_id);
+    assert_eq!(event.depth(), 1);
+    assert_eq!(event.frame(), 1);
+    assert_eq!(*event.cast::<u32>().unwrap(), 7);
+
+    // the events of a failed nested call are dropped along with it
+    let (receipt, _) = network
+        .transact(delegator_id, 0, forward(8, true), &mut gas)
+        .expect("The delegator should handle the error of the callee");
+
+    assert!(matches!(*receipt, Err(CallError::Panic(_))));
+    assert!(receipt.events().is_empty());
+    assert!(!receipt.bloom().contains_origin(&contract_id));
+}
+
 #[test]
 fn fibonacci() {
     use fibonacci::Fibonacci;
@@ -322,6 +466,207 @@ fn self_snapshot() {
     );
 }
 
+#[test]
+fn self_snapshot_nested_panic() {
+    use delegator::TryTransactionForwardData;
+    use rusk_uplink::CallError;
+    use rusk_vm::VMError;
+
+    let self_snapshot = SelfSnapshot::new(7);
+    let delegator = Delegator;
+
+    let mut network = NetworkState::new();
+
+    let code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/self_snapshot.wasm"
+    );
+    let delegator_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/delegator.wasm"
+    );
+
+    let contract =
+        Contract::new(&self_snapshot, code.to_vec(), network.store());
+    let delegator_contract =
+        Contract::new(&delegator, delegator_code.to_vec(), network.store());
+
+    let contract_id = network.deploy(contract).unwrap();
+    let delegator_id = network.deploy(delegator_contract).unwrap();
+
+    let update_and_panic = self_snapshot::UpdateAndPanicTransaction::new(11);
+    use rkyv::ser::serializers::BufferSerializer;
+    use rkyv::ser::Serializer;
+    use rkyv::Archive;
+
+    let mut buf = [0u8; 128];
+    let mut ser = BufferSerializer::new(&mut buf);
+    let buffer_len = ser.serialize_value(&update_and_panic).unwrap()
+        + core::mem::size_of::<
+            <::self_snapshot::UpdateAndPanicTransaction as Archive>::Archived,
+        >();
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    let result = network.transact(
+        delegator_id,
+        0,
+        TransactionForwardData::new(
+            contract_id,
+            &buf[..buffer_len],
+            "update_and_panic",
+        ),
+        &mut gas,
+    );
+
+    // the error of the failing callee is propagated as is, rather than as a
+    // panic of the delegator
+    match result {
+        Err(VMError::ContractPanic(id, msg)) => {
+            assert_eq!(id, contract_id);
+            assert!(msg.contains("OH NOES"), "unexpected message: {}", msg);
+        }
+        other => panic!("Expected a panic of the callee, got {:?}", other),
+    }
+
+    // when the delegator catches the failure, its own call commits while the
+    // writes of the failed frame - including the ones of the nested call it
+    // made before panicking - are reverted
+    let (receipt, network) = network
+        .transact(
+            delegator_id,
+            0,
+            TryTransactionForwardData::new(
+                contract_id,
+                &buf[..buffer_len],
+                "update_and_panic",
+            ),
+            &mut gas,
+        )
+        .expect("The delegator should handle the error of the callee");
+
+    assert!(matches!(*receipt, Err(CallError::Panic(_))));
+    assert_eq!(
+        7,
+        *network
+            .query(contract_id, 0, self_snapshot::CrossoverQuery, &mut gas)
+            .unwrap()
+    );
+
+    // a nested call that succeeds is committed to its caller
+    let set_crossover = self_snapshot::SetCrossoverTransaction::new(11);
+
+    let mut buf = [0u8; 128];
+    let mut ser = BufferSerializer::new(&mut buf);
+    let buffer_len = ser.serialize_value(&set_crossover).unwrap()
+        + core::mem::size_of::<
+            <::self_snapshot::SetCrossoverTransaction as Archive>::Archived,
+        >();
+
+    let (_, network) = network
+        .transact(
+            delegator_id,
+            0,
+            TryTransactionForwardData::new(
+                contract_id,
+                &buf[..buffer_len],
+                "set_crossover",
+            ),
+            &mut gas,
+        )
+        .expect("The delegator should succeed");
+
+    assert_eq!(
+        11,
+        *network
+            .query(contract_id, 0, self_snapshot::CrossoverQuery, &mut gas)
+            .unwrap()
+    );
+}
+
+#[test]
+fn delegated_call_error() {
+    use delegator::TryTransactionForwardData;
+    use rusk_uplink::CallError;
+    use rusk_vm::ContractId;
+
+    let self_snapshot = SelfSnapshot::new(7);
+    let delegator = Delegator;
+
+    let mut network = NetworkState::new();
+
+    let code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/self_snapshot.wasm"
+    );
+    let delegator_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/delegator.wasm"
+    );
+
+    let contract =
+        Contract::new(&self_snapshot, code.to_vec(), network.store());
+    let delegator_contract =
+        Contract::new(&delegator, delegator_code.to_vec(), network.store());
+
+    let contract_id = network.deploy(contract).unwrap();
+    let delegator_id = network.deploy(delegator_contract).unwrap();
+
+    let update_and_panic = self_snapshot::UpdateAndPanicTransaction::new(11);
+    use rkyv::ser::serializers::BufferSerializer;
+    use rkyv::ser::Serializer;
+    use rkyv::Archive;
+
+    let mut buf = [0u8; 128];
+    let mut ser = BufferSerializer::new(&mut buf);
+    let buffer_len = ser.serialize_value(&update_and_panic).unwrap()
+        + core::mem::size_of::<
+            <::self_snapshot::UpdateAndPanicTransaction as Archive>::Archived,
+        >();
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    // the delegator catches the panic of the callee and succeeds
+    let (receipt, network) = network
+        .transact(
+            delegator_id,
+            0,
+            TryTransactionForwardData::new(
+                contract_id,
+                &buf[..buffer_len],
+                "update_and_panic",
+            ),
+            &mut gas,
+        )
+        .expect("The delegator should handle the error of the callee");
+
+    match &*receipt {
+        Err(CallError::Panic(msg)) => {
+            assert!(msg.contains("OH NOES"), "unexpected message: {}", msg)
+        }
+        other => panic!("Expected a panic of the callee, got {:?}", other),
+    }
+
+    // the nested update made before the panic is rolled back
+    assert_eq!(
+        7,
+        *network
+            .query(contract_id, 0, self_snapshot::CrossoverQuery, &mut gas)
+            .unwrap()
+    );
+
+    let (receipt, _) = network
+        .transact(
+            delegator_id,
+            0,
+            TryTransactionForwardData::new(
+                ContractId::reserved(0xff),
+                &buf[..buffer_len],
+                "update_and_panic",
+            ),
+            &mut gas,
+        )
+        .expect("The delegator should handle the error of the callee");
+
+    assert_eq!(*receipt, Err(CallError::UnknownContract));
+}
+
 #[test]
 fn tx_vec() {
     let value = 15;
@@ -383,64 +728,188 @@ fn tx_vec() {
 }
 
 #[test]
-fn calling() {
-    let caller = CallerState::new();
-    let callee1 = Callee1State::new();
-    let callee2 = Callee2State::new();
+fn large_arguments() {
+    let tx_vec = TxVec::new(0);
 
     let mut network = NetworkState::new();
 
-    let code_caller =
-        include_bytes!("../target/wasm32-unknown-unknown/release/caller.wasm");
-    let code_callee1 = include_bytes!(
-        "../target/wasm32-unknown-unknown/release/callee_1.wasm"
-    );
-    let code_callee2 = include_bytes!(
-        "../target/wasm32-unknown-unknown/release/callee_2.wasm"
-    );
+    let code =
+        include_bytes!("../target/wasm32-unknown-unknown/release/tx_vec.wasm");
 
-    let contract0 =
-        Contract::new(&caller, code_caller.to_vec(), network.store());
-    let contract1 =
-        Contract::new(&callee1, code_callee1.to_vec(), network.store());
-    let contract2 =
-        Contract::new(&callee2, code_callee2.to_vec(), network.store());
-    let caller_id = network.deploy(contract0).unwrap();
-    let callee1_id = network.deploy(contract1).unwrap();
-    let callee2_id = network.deploy(contract2).unwrap();
+    let contract = Contract::new(&tx_vec, code.to_vec(), network.store());
+    let contract_id = network.deploy(contract).unwrap();
 
     let mut gas = GasMeter::with_limit(1_000_000_000);
 
+    // Arguments larger than the initial scratch buffer of the contract
+    let values = (0..200_000)
+        .map(|i: u32| (i % 251) as u8)
+        .collect::<Vec<_>>();
+    let sum = values.iter().fold(0u8, |s, v| s.wrapping_add(*v));
+
     let (_, network) = network
-        .transact(caller_id, 0, CallerTransaction::new(callee1_id), &mut gas)
+        .transact(contract_id, 0, TxVecSum::new(&values), &mut gas)
+        .expect("Transaction with a large argument should succeed");
+
+    let v = *network
+        .query(contract_id, 0, TxVecReadValue, &mut gas)
         .unwrap();
+    assert_eq!(v, sum);
 
+    // Also larger than the buffer used for calls between contracts
+    let delegate_sum = TxVecDelegateSum::new(contract_id, &values);
     let (_, network) = network
-        .transact(callee1_id, 0, Callee1Transaction::new(callee2_id), &mut gas)
-        .unwrap();
+        .transact(contract_id, 0, delegate_sum, &mut gas)
+        .expect("Delegating a large argument should succeed");
 
-    assert_eq!(
-        *network.query(caller_id, 0, CallerQuery, &mut gas).unwrap(),
-        (
-            caller_id.as_array(),
-            callee1_id.as_array(),
-            callee2_id.as_array()
-        ),
-        "Expected Callers and Callees"
-    )
+    let v = *network
+        .query(contract_id, 0, TxVecReadValue, &mut gas)
+        .unwrap();
+    assert_eq!(v, sum.wrapping_add(sum));
 }
 
 #[test]
-fn gas_consumed_host_function_works() {
-    let gas_contract = GasConsumed::new(99);
-
+fn large_results() {
     let mut network = NetworkState::new();
 
-    let code = include_bytes!(
-        "../target/wasm32-unknown-unknown/release/gas_consumed.wasm"
-    );
+    let code =
+        include_bytes!("../target/wasm32-unknown-unknown/release/tx_vec.wasm");
 
-    let contract = Contract::new(&gas_contract, code.to_vec(), network.store());
+    let caller = Contract::new(&TxVec::new(0), code.to_vec(), network.store());
+    let callee = Contract::new(&TxVec::new(7), code.to_vec(), network.store());
+
+    let caller_id = network.deploy(caller).unwrap();
+    let callee_id = network.deploy(callee).unwrap();
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    // Results larger than the buffer used for calls between contracts
+    let len = 100_000;
+
+    let repeated = network
+        .query(
+            caller_id,
+            0,
+            TxVecDelegateRepeat::new(callee_id, len),
+            &mut gas,
+        )
+        .expect("Delegating a query with a large result should succeed");
+    assert_eq!(*repeated, vec![7; len as usize].into_boxed_slice());
+
+    let values = (0..len).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+    let sum = values.iter().fold(7u8, |s, v| s.wrapping_add(*v));
+
+    let (echoed, network) = network
+        .transact(
+            caller_id,
+            0,
+            TxVecDelegateEcho::new(callee_id, &values),
+            &mut gas,
+        )
+        .expect("Delegating a transaction with a large result should succeed");
+    assert_eq!(*echoed, values.into_boxed_slice());
+
+    // The transaction is applied once, even though it had to be retried
+    let v = *network
+        .query(callee_id, 0, TxVecReadValue, &mut gas)
+        .unwrap();
+    assert_eq!(v, sum);
+}
+
+#[test]
+fn large_state_growth() {
+    let mut network = NetworkState::new();
+
+    let code =
+        include_bytes!("../target/wasm32-unknown-unknown/release/blob.wasm");
+
+    let contract = Contract::new(&Blob::new(), code.to_vec(), network.store());
+    let contract_id = network.deploy(contract).unwrap();
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    // The new state outgrows the room the scratch buffer leaves past the
+    // current state and the argument
+    let len = 200_000;
+
+    let (written, network) = network
+        .transact(contract_id, 0, BlobAppend::new(3, len), &mut gas)
+        .expect("Growing the state past the scratch buffer should succeed");
+    assert_eq!(*written, len);
+
+    // Also when the state is already larger than the scratch buffer
+    let (written, network) = network
+        .transact(contract_id, 0, BlobAppend::new(5, len), &mut gas)
+        .expect("Growing a large state further should succeed");
+    assert_eq!(*written, 2 * len);
+
+    // The result of a query can outgrow the buffer just as well
+    let bytes = network.query(contract_id, 0, BlobRead, &mut gas).unwrap();
+
+    let mut expected = vec![3; len as usize];
+    expected.resize(2 * len as usize, 5);
+    assert_eq!(bytes.as_slice(), &expected[..]);
+}
+
+#[test]
+fn calling() {
+    let caller = CallerState::new();
+    let callee1 = Callee1State::new();
+    let callee2 = Callee2State::new();
+
+    let mut network = NetworkState::new();
+
+    let code_caller =
+        include_bytes!("../target/wasm32-unknown-unknown/release/caller.wasm");
+    let code_callee1 = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/callee_1.wasm"
+    );
+    let code_callee2 = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/callee_2.wasm"
+    );
+
+    let contract0 =
+        Contract::new(&caller, code_caller.to_vec(), network.store());
+    let contract1 =
+        Contract::new(&callee1, code_callee1.to_vec(), network.store());
+    let contract2 =
+        Contract::new(&callee2, code_callee2.to_vec(), network.store());
+    let caller_id = network.deploy(contract0).unwrap();
+    let callee1_id = network.deploy(contract1).unwrap();
+    let callee2_id = network.deploy(contract2).unwrap();
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    let (_, network) = network
+        .transact(caller_id, 0, CallerTransaction::new(callee1_id), &mut gas)
+        .unwrap();
+
+    let (_, network) = network
+        .transact(callee1_id, 0, Callee1Transaction::new(callee2_id), &mut gas)
+        .unwrap();
+
+    assert_eq!(
+        *network.query(caller_id, 0, CallerQuery, &mut gas).unwrap(),
+        (
+            caller_id.as_array(),
+            callee1_id.as_array(),
+            callee2_id.as_array()
+        ),
+        "Expected Callers and Callees"
+    )
+}
+
+#[test]
+fn gas_consumed_host_function_works() {
+    let gas_contract = GasConsumed::new(99);
+
+    let mut network = NetworkState::new();
+
+    let code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/gas_consumed.wasm"
+    );
+
+    let contract = Contract::new(&gas_contract, code.to_vec(), network.store());
     let contract_id = network.deploy(contract).expect("Deploy error");
 
     // 2050 is the gas held that is known will be spent in the contract
@@ -670,3 +1139,1043 @@ fn map() {
             .expect("Query should not error")
     );
 }
+
+#[test]
+fn contract_cast_state() {
+    use rusk_vm::{ContractId, VMError};
+    use stack::{Push, Stack};
+
+    let mut network = NetworkState::new();
+
+    let counter_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+    let stack_code =
+        include_bytes!("../target/wasm32-unknown-unknown/release/stack.wasm");
+
+    let counter_contract = Contract::new(
+        &Counter::new(99),
+        counter_code.to_vec(),
+        network.store(),
+    );
+    let stack_contract =
+        Contract::new(&Stack::new(), stack_code.to_vec(), network.store());
+
+    let counter_id = network.deploy(counter_contract).unwrap();
+    let stack_id = network.deploy(stack_contract).unwrap();
+
+    let counter: Counter = network
+        .get_contract_cast_state(&counter_id)
+        .expect("Counter state should be readable");
+    assert_eq!(counter.read_value(), 99);
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    let (_, mut network) = network
+        .transact(counter_id, 0, counter::Increment, &mut gas)
+        .unwrap();
+
+    const N: u64 = 16;
+    for i in 0..N {
+        network = network
+            .transact(stack_id, 0, Push::new(i), &mut gas)
+            .unwrap()
+            .1;
+    }
+
+    let counter: Counter = network
+        .get_contract_cast_state(&counter_id)
+        .expect("Counter state should be readable");
+    assert_eq!(counter.read_value(), 100);
+
+    let stack: Stack = network
+        .get_contract_cast_state(&stack_id)
+        .expect("Stack state should be readable");
+    for i in 0..N {
+        assert_eq!(stack.peek(i), Some(i));
+    }
+    assert_eq!(stack.peek(N), None);
+
+    assert!(matches!(
+        network.get_contract_cast_state::<Stack>(&counter_id),
+        Err(VMError::InvalidData)
+    ));
+
+    let unknown_id = ContractId::reserved(0xff);
+    assert!(matches!(
+        network.get_contract_cast_state::<Counter>(&unknown_id),
+        Err(VMError::UnknownContract(id)) if id == unknown_id
+    ));
+}
+
+#[test]
+fn deploy_from_contract() {
+    use factory::{Deploy, Factory};
+    use minimal_counter::{Counter, Increment, ReadCount};
+
+    let mut network = NetworkState::new();
+
+    let factory_code =
+        include_bytes!("../target/wasm32-unknown-unknown/release/factory.wasm");
+    let counter_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/minimal_counter.wasm"
+    );
+
+    let factory_contract =
+        Contract::new(&Factory, factory_code.to_vec(), network.store());
+    let factory_id = network.deploy(factory_contract).unwrap();
+    let original = network.clone();
+
+    // Serialize the initial state of the counter the same way a contract
+    // being deployed by the host would be.
+    let init_state =
+        Contract::new(&Counter::new(7), counter_code.to_vec(), network.store())
+            .state()
+            .to_vec();
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    let (receipt, mut network) = network
+        .transact(
+            factory_id,
+            0,
+            Deploy::new(&counter_code[..], &init_state),
+            &mut gas,
+        )
+        .expect("Deploying from a contract should succeed");
+    let counter_id = *receipt;
+
+    // The id only depends on the deployer and its nonce
+    let (receipt, _) = original
+        .transact(
+            factory_id,
+            0,
+            Deploy::new(&counter_code[..], &init_state),
+            &mut gas,
+        )
+        .unwrap();
+    assert_eq!(*receipt, counter_id);
+
+    assert_eq!(
+        *network
+            .query(counter_id, 0, ReadCount, &mut gas)
+            .expect("Query to the deployed contract should succeed"),
+        7
+    );
+
+    network = network
+        .transact(counter_id, 0, Increment(1), &mut gas)
+        .unwrap()
+        .1;
+    assert_eq!(
+        *network.query(counter_id, 0, ReadCount, &mut gas).unwrap(),
+        8
+    );
+
+    // Every deployment bumps the nonce of the factory, resulting in a new id
+    let (receipt, network) = network
+        .transact(
+            factory_id,
+            0,
+            Deploy::new(&counter_code[..], &init_state),
+            &mut gas,
+        )
+        .unwrap();
+    let second_id = *receipt;
+
+    assert_ne!(counter_id, second_id);
+    assert_eq!(
+        *network.query(second_id, 0, ReadCount, &mut gas).unwrap(),
+        7
+    );
+
+    // Replacing the factory keeps its nonce, so it does not deploy to the ids
+    // it deployed to before
+    let mut network = network;
+    let upgrade =
+        Contract::new(&Factory, factory_code.to_vec(), network.store());
+    network
+        .replace_contract(factory_id, upgrade)
+        .expect("Replacing the factory should succeed");
+
+    let (receipt, network) = network
+        .transact(
+            factory_id,
+            0,
+            Deploy::new(&counter_code[..], &init_state),
+            &mut gas,
+        )
+        .expect("Deploying from a replaced contract should succeed");
+    let third_id = *receipt;
+
+    assert_ne!(third_id, counter_id);
+    assert_ne!(third_id, second_id);
+    assert_eq!(
+        *network.query(counter_id, 0, ReadCount, &mut gas).unwrap(),
+        8
+    );
+}
+
+#[test]
+fn deploy_from_failed_call() {
+    use delegator::TryTransactionForwardData;
+    use factory::{Deploy, DeployAndPanic, DeployInQuery, Factory};
+    use minimal_counter::{Counter, ReadCount};
+    use rkyv::ser::serializers::AllocSerializer;
+    use rkyv::ser::Serializer;
+    use rusk_uplink::CallError;
+    use rusk_vm::VMError;
+
+    let mut network = NetworkState::new();
+
+    let factory_code =
+        include_bytes!("../target/wasm32-unknown-unknown/release/factory.wasm");
+    let delegator_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/delegator.wasm"
+    );
+    let counter_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/minimal_counter.wasm"
+    );
+
+    let factory_contract =
+        Contract::new(&Factory, factory_code.to_vec(), network.store());
+    let delegator_contract =
+        Contract::new(&Delegator, delegator_code.to_vec(), network.store());
+
+    let factory_id = network.deploy(factory_contract).unwrap();
+    let delegator_id = network.deploy(delegator_contract).unwrap();
+
+    let init_state =
+        Contract::new(&Counter::new(7), counter_code.to_vec(), network.store())
+            .state()
+            .to_vec();
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    // Queries cannot deploy contracts
+    assert!(matches!(
+        network.query(
+            factory_id,
+            0,
+            DeployInQuery::new(&counter_code[..], &init_state),
+            &mut gas,
+        ),
+        Err(VMError::StateChangeInQuery(id)) if id == factory_id
+    ));
+
+    // A deployment is reverted along with the call that made it, even when
+    // its caller handles the error and succeeds
+    let mut ser = AllocSerializer::<0>::default();
+    ser.serialize_value(&DeployAndPanic::new(&counter_code[..], &init_state))
+        .unwrap();
+    let data = ser.into_serializer().into_inner();
+
+    let root = network.root();
+    let (receipt, network) = network
+        .transact(
+            delegator_id,
+            0,
+            TryTransactionForwardData::new(
+                factory_id,
+                &data[..],
+                "deploy_and_panic",
+            ),
+            &mut gas,
+        )
+        .expect("The delegator should handle the error of the factory");
+    assert!(matches!(*receipt, Err(CallError::Panic(_))));
+
+    assert_eq!(network.root(), root);
+    assert_eq!(network.prove_contract(&factory_id).unwrap().nonce, 0);
+
+    // The reverted deployment leaves its id and nonce free for the next one
+    let (receipt, network) = network
+        .transact(
+            factory_id,
+            0,
+            Deploy::new(&counter_code[..], &init_state),
+            &mut gas,
+        )
+        .expect("Deploying from a contract should succeed");
+    let counter_id = *receipt;
+
+    assert_eq!(
+        *network.query(counter_id, 0, ReadCount, &mut gas).unwrap(),
+        7
+    );
+
+    // The nonce is committed to in the proofs of the deployer's state
+    let proof = network.prove_contract(&factory_id).unwrap();
+    assert_eq!(proof.nonce, 1);
+
+    let factory_state =
+        Contract::new(&Factory, factory_code.to_vec(), network.store())
+            .state()
+            .to_vec();
+    let root = network.root();
+    assert!(proof.verify(&root, &factory_id, &factory_state));
+
+    let mut tampered = proof.clone();
+    tampered.nonce = 0;
+    assert!(!tampered.verify(&root, &factory_id, &factory_state));
+}
+
+#[test]
+fn upgrade_from_contract() {
+    use factory::{Deploy, Factory, Upgrade};
+    use minimal_counter::Counter;
+    use rusk_uplink::code_hash;
+    use rusk_vm::VMError;
+
+    let mut network = NetworkState::new();
+
+    let factory_code =
+        include_bytes!("../target/wasm32-unknown-unknown/release/factory.wasm");
+    let delegator_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/delegator.wasm"
+    );
+    let counter_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/minimal_counter.wasm"
+    );
+
+    let factory_contract =
+        Contract::new(&Factory, factory_code.to_vec(), network.store());
+    let factory_id = network.deploy(factory_contract).unwrap();
+
+    let init_state =
+        Contract::new(&Counter::new(7), counter_code.to_vec(), network.store())
+            .state()
+            .to_vec();
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    let (_, network) = network
+        .transact(
+            factory_id,
+            0,
+            Deploy::new(&counter_code[..], &init_state),
+            &mut gas,
+        )
+        .expect("Deploying from a contract should succeed");
+
+    // Bytecode that does not compile is rejected
+    assert!(network
+        .transact(factory_id, 0, Upgrade::new(&[0xde, 0xad]), &mut gas)
+        .is_err());
+
+    // The delegator keeps its state in the same unit struct as the factory
+    let (_, network) = network
+        .transact(factory_id, 0, Upgrade::new(&delegator_code[..]), &mut gas)
+        .expect("Upgrading from a contract should succeed");
+
+    let proof = network.prove_contract(&factory_id).unwrap();
+    assert_eq!(proof.code_hash, code_hash(&delegator_code[..]));
+    assert_eq!(proof.nonce, 1);
+
+    assert!(matches!(
+        network.transact(
+            factory_id,
+            0,
+            Deploy::new(&counter_code[..], &init_state),
+            &mut gas,
+        ),
+        Err(VMError::UnknownMethod { contract, .. }) if contract == factory_id
+    ));
+}
+
+#[test]
+fn remove_contract() {
+    use rusk_vm::{ContractId, VMError};
+
+    let mut network = NetworkState::new();
+
+    let counter_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+
+    let empty_root = network.root();
+
+    let contract = Contract::new(
+        &Counter::new(99),
+        counter_code.to_vec(),
+        network.store(),
+    );
+    let contract_id = network.deploy(contract).unwrap();
+
+    assert_ne!(network.root(), empty_root);
+
+    network
+        .remove_contract(&contract_id)
+        .expect("Removing a deployed contract should succeed");
+
+    assert_eq!(network.root(), empty_root);
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    assert!(matches!(
+        network.query(contract_id, 0, counter::ReadValue, &mut gas),
+        Err(VMError::UnknownContract(id)) if id == contract_id
+    ));
+    assert!(matches!(
+        network.remove_contract(&contract_id),
+        Err(VMError::UnknownContract(id)) if id == contract_id
+    ));
+
+    let unknown_id = ContractId::reserved(0xff);
+    assert!(matches!(
+        network.remove_contract(&unknown_id),
+        Err(VMError::UnknownContract(id)) if id == unknown_id
+    ));
+}
+
+#[test]
+fn self_destruct() {
+    use counter::{ReadValue, SelfDestruct};
+    use rusk_vm::VMError;
+
+    let mut network = NetworkState::new();
+
+    let counter_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+
+    let empty_root = network.root();
+
+    let contract = Contract::new(
+        &Counter::new(99),
+        counter_code.to_vec(),
+        network.store(),
+    );
+    let contract_id = network.deploy(contract).unwrap();
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    let (_, network) = network
+        .transact(contract_id, 0, SelfDestruct, &mut gas)
+        .expect("Self destruct should succeed");
+
+    assert_eq!(network.root(), empty_root);
+
+    assert!(matches!(
+        network.query(contract_id, 0, ReadValue, &mut gas),
+        Err(VMError::UnknownContract(id)) if id == contract_id
+    ));
+    assert!(matches!(
+        network.transact(contract_id, 0, SelfDestruct, &mut gas),
+        Err(VMError::UnknownContract(id)) if id == contract_id
+    ));
+}
+
+#[test]
+fn self_destruct_in_query() {
+    use counter::{DestructInQuery, ReadValue};
+    use delegator::TryQueryForwardData;
+    use rkyv::ser::serializers::AllocSerializer;
+    use rkyv::ser::Serializer;
+    use rusk_uplink::CallError;
+    use rusk_vm::VMError;
+
+    let mut network = NetworkState::new();
+
+    let counter_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+    let delegator_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/delegator.wasm"
+    );
+
+    let contract = Contract::new(
+        &Counter::new(99),
+        counter_code.to_vec(),
+        network.store(),
+    );
+    let contract_id = network.deploy(contract).unwrap();
+    let delegator =
+        Contract::new(&Delegator, delegator_code.to_vec(), network.store());
+    let delegator_id = network.deploy(delegator).unwrap();
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    assert!(matches!(
+        network.query(contract_id, 0, DestructInQuery, &mut gas),
+        Err(VMError::StateChangeInQuery(id)) if id == contract_id
+    ));
+
+    // A contract queried from a transaction cannot remove itself either
+    let mut ser = AllocSerializer::<0>::default();
+    ser.serialize_value(&DestructInQuery).unwrap();
+    let data = ser.into_serializer().into_inner();
+
+    let root = network.root();
+    let (receipt, network) = network
+        .transact(
+            delegator_id,
+            0,
+            TryQueryForwardData::new(
+                contract_id,
+                &data[..],
+                "destruct_in_query",
+            ),
+            &mut gas,
+        )
+        .expect("The delegator should handle the error of the callee");
+    assert!(matches!(*receipt, Err(CallError::Panic(_))));
+
+    assert_eq!(network.root(), root);
+    assert_eq!(
+        *network.query(contract_id, 0, ReadValue, &mut gas).unwrap(),
+        99
+    );
+}
+
+#[test]
+fn deploy_existing_contract() {
+    use counter::ReadValue;
+    use rusk_vm::VMError;
+
+    let mut network = NetworkState::new();
+
+    let counter_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+
+    let contract = |value| {
+        Contract::new(
+            &Counter::new(value),
+            counter_code.to_vec(),
+            network.store(),
+        )
+    };
+    let (first, second, third) = (contract(1), contract(2), contract(1));
+
+    let first_id = network.deploy(first.clone()).unwrap();
+    // Same code with a different initial state results in a different id
+    let second_id = network.deploy(second).unwrap();
+    assert_ne!(first_id, second_id);
+
+    // The same code and initial state is rejected, unless salted
+    assert!(matches!(
+        network.deploy(third.clone()),
+        Err(VMError::ContractAlreadyExists(id)) if id == first_id
+    ));
+    let third_id = network.deploy_with_salt(third, b"salt").unwrap();
+    assert_ne!(first_id, third_id);
+
+    assert!(matches!(
+        network.deploy_with_id(second_id, first),
+        Err(VMError::ContractAlreadyExists(id)) if id == second_id
+    ));
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    assert_eq!(*network.query(first_id, 0, ReadValue, &mut gas).unwrap(), 1);
+    assert_eq!(
+        *network.query(second_id, 0, ReadValue, &mut gas).unwrap(),
+        2
+    );
+    assert_eq!(*network.query(third_id, 0, ReadValue, &mut gas).unwrap(), 1);
+}
+
+#[test]
+fn replace_contract() {
+    use counter::ReadValue;
+    use minimal_counter::ReadCount;
+    use rusk_vm::{ContractId, VMError};
+
+    let mut network = NetworkState::new();
+
+    let counter_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+    let minimal_counter_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/minimal_counter.wasm"
+    );
+
+    let contract = Contract::new(
+        &Counter::new(99),
+        counter_code.to_vec(),
+        network.store(),
+    );
+    let contract_id = network.deploy(contract).unwrap();
+
+    let upgrade = Contract::new(
+        &minimal_counter::Counter::new(42),
+        minimal_counter_code.to_vec(),
+        network.store(),
+    );
+
+    let unknown_id = ContractId::reserved(0xff);
+    assert!(matches!(
+        network.replace_contract(unknown_id, upgrade.clone()),
+        Err(VMError::UnknownContract(id)) if id == unknown_id
+    ));
+
+    let previous = network
+        .replace_contract(contract_id, upgrade)
+        .expect("Replacing an existing contract should succeed");
+    assert_eq!(previous.bytecode(), &counter_code[..]);
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    assert_eq!(
+        *network.query(contract_id, 0, ReadCount, &mut gas).unwrap(),
+        42
+    );
+    assert!(network.query(contract_id, 0, ReadValue, &mut gas).is_err());
+}
+
+#[test]
+fn contract_storage() {
+    use delegator::TryTransactionForwardData;
+    use rkyv::ser::serializers::BufferSerializer;
+    use rkyv::ser::Serializer;
+    use rkyv::Archive;
+    use rusk_uplink::CallError;
+    use storage::{Get, KeyValue, Remove, Set, SetAndPanic};
+
+    let mut network = NetworkState::new();
+
+    let code =
+        include_bytes!("../target/wasm32-unknown-unknown/release/storage.wasm");
+
+    let contract = Contract::new(&KeyValue, code.to_vec(), network.store());
+    let contract_id = network.deploy(contract).unwrap();
+    let empty_root = network.root();
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    // Larger than the buffer the value is first read into
+    let value = vec![0xab; 1000];
+
+    let (_, network) = network
+        .transact(contract_id, 0, Set::new(b"answer", &value), &mut gas)
+        .expect("Storing a value should succeed");
+    assert_ne!(network.root(), empty_root);
+
+    assert_eq!(
+        *network
+            .query(contract_id, 0, Get::new(b"answer"), &mut gas)
+            .unwrap(),
+        Some(value.clone())
+    );
+    assert_eq!(
+        *network
+            .query(contract_id, 0, Get::new(b"question"), &mut gas)
+            .unwrap(),
+        None
+    );
+
+    // The storage is committed to in the proofs of the contract's state
+    let proof = network.prove_contract(&contract_id).unwrap();
+    assert_ne!(proof.storage_root, [0; 32]);
+
+    // The writes of a failed nested call are reverted, even when its caller
+    // handles the error and succeeds
+    let delegator_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/delegator.wasm"
+    );
+    let delegator_contract =
+        Contract::new(&Delegator, delegator_code.to_vec(), network.store());
+    let mut nested = network.clone();
+    let delegator_id = nested.deploy(delegator_contract).unwrap();
+
+    let root = nested.root();
+    let proof = nested.prove_contract(&contract_id).unwrap();
+
+    for key in [&b"answer"[..], &b"question"[..]] {
+        let mut buf = [0u8; 128];
+        let mut ser = BufferSerializer::new(&mut buf);
+        let buffer_len =
+            ser.serialize_value(&SetAndPanic::new(key, b"?")).unwrap()
+                + core::mem::size_of::<<SetAndPanic as Archive>::Archived>();
+
+        let (receipt, forked) = nested
+            .transact(
+                delegator_id,
+                0,
+                TryTransactionForwardData::new(
+                    contract_id,
+                    &buf[..buffer_len],
+                    "set_and_panic",
+                ),
+                &mut gas,
+            )
+            .expect("The delegator should handle the error of the callee");
+        assert!(matches!(*receipt, Err(CallError::Panic(_))));
+
+        assert_eq!(forked.root(), root);
+        assert_eq!(forked.prove_contract(&contract_id).unwrap(), proof);
+        assert_eq!(
+            *forked
+                .query(contract_id, 0, Get::new(b"answer"), &mut gas)
+                .unwrap(),
+            Some(value.clone())
+        );
+        assert_eq!(
+            *forked
+                .query(contract_id, 0, Get::new(b"question"), &mut gas)
+                .unwrap(),
+            None
+        );
+    }
+
+    let (receipt, network) = network
+        .transact(contract_id, 0, Remove::new(b"answer"), &mut gas)
+        .expect("Removing a value should succeed");
+    assert!(*receipt);
+    assert_eq!(network.root(), empty_root);
+
+    assert_eq!(
+        *network
+            .query(contract_id, 0, Get::new(b"answer"), &mut gas)
+            .unwrap(),
+        None
+    );
+
+    let (receipt, _) = network
+        .transact(contract_id, 0, Remove::new(b"answer"), &mut gas)
+        .unwrap();
+    assert!(!*receipt);
+}
+
+#[test]
+fn storage_write_in_query() {
+    use delegator::TryQueryForwardData;
+    use rkyv::ser::serializers::AllocSerializer;
+    use rkyv::ser::Serializer;
+    use rusk_uplink::CallError;
+    use rusk_vm::VMError;
+    use storage::{Get, KeyValue, SetInQuery};
+
+    let mut network = NetworkState::new();
+
+    let code =
+        include_bytes!("../target/wasm32-unknown-unknown/release/storage.wasm");
+    let delegator_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/delegator.wasm"
+    );
+
+    let contract = Contract::new(&KeyValue, code.to_vec(), network.store());
+    let contract_id = network.deploy(contract).unwrap();
+    let delegator =
+        Contract::new(&Delegator, delegator_code.to_vec(), network.store());
+    let delegator_id = network.deploy(delegator).unwrap();
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    assert!(matches!(
+        network.query(
+            contract_id,
+            0,
+            SetInQuery::new(b"answer", b"42"),
+            &mut gas
+        ),
+        Err(VMError::StateChangeInQuery(id)) if id == contract_id
+    ));
+
+    // A contract queried from a transaction cannot write either
+    let mut ser = AllocSerializer::<0>::default();
+    ser.serialize_value(&SetInQuery::new(b"answer", b"42"))
+        .unwrap();
+    let data = ser.into_serializer().into_inner();
+
+    let root = network.root();
+    let (receipt, network) = network
+        .transact(
+            delegator_id,
+            0,
+            TryQueryForwardData::new(contract_id, &data[..], "set_in_query"),
+            &mut gas,
+        )
+        .expect("The delegator should handle the error of the callee");
+    assert!(matches!(*receipt, Err(CallError::Panic(_))));
+
+    assert_eq!(network.root(), root);
+    assert_eq!(
+        *network
+            .query(contract_id, 0, Get::new(b"answer"), &mut gas)
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn out_of_bounds_memory_access() {
+    use adversarial::{Adversarial, Attack, HOST_FUNCTIONS, OUT_OF_BOUNDS};
+    use rusk_vm::VMError;
+
+    let mut network = NetworkState::new();
+
+    let code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/adversarial.wasm"
+    );
+
+    let contract = Contract::new(&Adversarial, code.to_vec(), network.store());
+    let contract_id = network.deploy(contract).unwrap();
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    for (i, name) in HOST_FUNCTIONS.iter().enumerate() {
+        let result =
+            network.transact(contract_id, 0, Attack::new(i as u8), &mut gas);
+
+        assert!(
+            matches!(
+                result,
+                Err(VMError::MemoryAccessOutOfBounds { offset, .. })
+                    if offset == OUT_OF_BOUNDS as u64
+            ),
+            "'{}' should fail with an out of bounds access",
+            name
+        );
+    }
+}
+
+/// Builds a wasm module importing a single function taking no parameters and
+/// returning nothing.
+fn module_importing(namespace: &str, name: &str) -> Vec<u8> {
+    let mut import = vec![1, namespace.len() as u8];
+    import.extend_from_slice(namespace.as_bytes());
+    import.push(name.len() as u8);
+    import.extend_from_slice(name.as_bytes());
+    import.extend_from_slice(&[0, 0]);
+
+    let mut module = b"\0asm\x01\0\0\0".to_vec();
+    // Type section holding the `() -> ()` function type
+    module.extend_from_slice(&[1, 4, 1, 0x60, 0, 0]);
+    // Import section
+    module.push(2);
+    module.push(import.len() as u8);
+    module.extend(import);
+    module
+}
+
+#[test]
+fn reject_invalid_modules() {
+    use rusk_vm::VMError;
+
+    let mut network = NetworkState::new();
+
+    let mut deploy = |code: Vec<u8>| {
+        let contract = Contract::new(&Counter::new(0), code, network.store());
+        network.deploy(contract)
+    };
+
+    assert!(matches!(
+        deploy(module_importing("env", "nonexistent")),
+        Err(VMError::UnknownImport { namespace, name })
+            if namespace == "env" && name == "nonexistent"
+    ));
+    assert!(matches!(
+        deploy(module_importing("other", "debug")),
+        Err(VMError::UnknownImport { namespace, name })
+            if namespace == "other" && name == "debug"
+    ));
+    // `block_height` takes no parameters but returns the height
+    assert!(matches!(
+        deploy(module_importing("canon", "block_height")),
+        Err(VMError::UnknownImport { namespace, name })
+            if namespace == "canon" && name == "block_height"
+    ));
+
+    // An empty module exports neither memory nor a scratch buffer
+    assert!(matches!(
+        deploy(b"\0asm\x01\0\0\0".to_vec()),
+        Err(VMError::MissingExport(export)) if export == "memory"
+    ));
+}
+
+#[test]
+fn contract_abi() {
+    use rusk_vm::EntryKind;
+
+    let mut network = NetworkState::new();
+
+    let code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+    let contract =
+        Contract::new(&Counter::new(99), code.to_vec(), network.store());
+    let contract_id = network.deploy(contract).expect("Deploy error");
+
+    let abi = network
+        .contract_abi(&contract_id)
+        .expect("Reading the ABI should succeed");
+
+    let queries: Vec<_> = abi.queries().map(|e| e.name()).collect();
+    assert_eq!(
+        queries,
+        vec!["destruct_in_query", "is_even", "read_value", "xor_values"]
+    );
+
+    let transactions: Vec<_> = abi.transactions().map(|e| e.name()).collect();
+    assert_eq!(
+        transactions,
+        vec![
+            "adjust",
+            "compare_and_swap",
+            "decrement",
+            "destruct",
+            "increment"
+        ]
+    );
+
+    let read_value = abi.get("read_value").expect("Query should be listed");
+    assert_eq!(read_value.kind(), EntryKind::Query);
+    assert_eq!(read_value.arg_type(), Some("ReadValue"));
+    assert_eq!(read_value.return_type(), Some("i32"));
+
+    let swap = abi.get("compare_and_swap").expect("Tx should be listed");
+    assert_eq!(swap.kind(), EntryKind::Transaction);
+    assert_eq!(swap.arg_type(), Some("CompareAndSwap"));
+    assert_eq!(swap.return_type(), Some("bool"));
+
+    let increment = abi.get("increment").expect("Tx should be listed");
+    assert_eq!(increment.return_type(), Some("()"));
+
+    // Neither the initializer nor the scratch buffer are entry points
+    assert!(abi.get("init").is_none());
+    assert!(abi.get("scratch_grow").is_none());
+
+    // Type names are recorded as they are written
+    let code =
+        include_bytes!("../target/wasm32-unknown-unknown/release/tx_vec.wasm");
+    let contract =
+        Contract::new(&TxVec::new(0), code.to_vec(), network.store());
+    let contract_id = network.deploy(contract).expect("Deploy error");
+
+    let abi = network.contract_abi(&contract_id).unwrap();
+    let repeat = abi.get("repeat").expect("Query should be listed");
+    assert_eq!(repeat.return_type(), Some("Box<[u8]>"));
+
+    let code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/delegator.wasm"
+    );
+    let contract = Contract::new(&Delegator, code.to_vec(), network.store());
+    let contract_id = network.deploy(contract).expect("Deploy error");
+
+    let abi = network.contract_abi(&contract_id).unwrap();
+    let try_delegate = abi
+        .get("try_delegate_transaction")
+        .expect("Tx should be listed");
+    assert_eq!(try_delegate.return_type(), Some("Result<(), CallError>"));
+
+    assert!(network
+        .contract_abi(&rusk_vm::ContractId::reserved(0xff))
+        .is_err());
+}
+
+#[test]
+fn unknown_method() {
+    use delegator::TryTransactionForwardData;
+    use rkyv::{Archive, Deserialize, Serialize};
+    use rusk_uplink::{CallError, Query, Transaction};
+    use rusk_vm::{EntryKind, VMError};
+
+    #[derive(Archive, Serialize, Deserialize)]
+    struct Nonexistent;
+
+    impl Query for Nonexistent {
+        const NAME: &'static str = "nonexistent";
+        type Return = ();
+    }
+
+    /// A transaction with the name of one of the counter's queries
+    #[derive(Archive, Serialize, Deserialize)]
+    struct ReadValueTransaction;
+
+    impl Transaction for ReadValueTransaction {
+        const NAME: &'static str = "read_value";
+        type Return = i32;
+    }
+
+    let mut network = NetworkState::new();
+
+    let code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+    let delegator_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/delegator.wasm"
+    );
+
+    let contract =
+        Contract::new(&Counter::new(99), code.to_vec(), network.store());
+    let delegator_contract =
+        Contract::new(&Delegator, delegator_code.to_vec(), network.store());
+
+    let contract_id = network.deploy(contract).unwrap();
+    let delegator_id = network.deploy(delegator_contract).unwrap();
+
+    // Both are caught before the contract is instantiated
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    assert!(matches!(
+        network.query(contract_id, 0, Nonexistent, &mut gas),
+        Err(VMError::UnknownMethod { contract, name })
+            if contract == contract_id && name == "nonexistent"
+    ));
+    assert!(matches!(
+        network.transact(contract_id, 0, ReadValueTransaction, &mut gas),
+        Err(VMError::MethodKindMismatch { contract, name, expected })
+            if contract == contract_id
+                && name == "read_value"
+                && expected == EntryKind::Transaction
+    ));
+    assert_eq!(gas.spent(), 0);
+
+    // Contracts calling the wrong method see the same errors
+    let (receipt, _) = network
+        .transact(
+            delegator_id,
+            0,
+            TryTransactionForwardData::new(
+                contract_id,
+                [0u8; 0],
+                "nonexistent",
+            ),
+            &mut gas,
+        )
+        .expect("The delegator should handle the error of the callee");
+    assert_eq!(*receipt, Err(CallError::UnknownMethod));
+
+    let (receipt, _) = network
+        .transact(
+            delegator_id,
+            0,
+            TryTransactionForwardData::new(contract_id, [0u8; 0], "read_value"),
+            &mut gas,
+        )
+        .expect("The delegator should handle the error of the callee");
+    assert_eq!(*receipt, Err(CallError::MethodKindMismatch));
+}
+
+#[test]
+fn hash() {
+    use blake2b_simd::Params;
+    use hasher::{HashBytes, Hasher};
+
+    let mut network = NetworkState::new();
+
+    let code =
+        include_bytes!("../target/wasm32-unknown-unknown/release/hasher.wasm");
+
+    let contract = Contract::new(&Hasher, code.to_vec(), network.store());
+    let contract_id = network.deploy(contract).expect("Deploy error");
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    let inputs: [&[u8]; 3] = [&[], b"rusk", &[0x5a; 100_000]];
+    for input in inputs {
+        let mut expected = [0u8; 32];
+        expected.copy_from_slice(
+            Params::new().hash_length(32).hash(input).as_bytes(),
+        );
+
+        let (host, guest) = *network
+            .query(contract_id, 0, HashBytes::new(input), &mut gas)
+            .expect("Query should succeed");
+
+        assert_eq!(host, expected);
+        assert_eq!(guest, expected);
+    }
+}
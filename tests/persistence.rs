@@ -0,0 +1,495 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use counter::{Counter, Increment, ReadValue};
+use rusk_vm::{Contract, GasMeter, NetworkState, HASH_VERSION};
+
+fn store_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "rusk-vm-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("Creating the store dir should succeed");
+    dir
+}
+
+#[test]
+fn persist_labeled_roots() {
+    let dir = store_dir("labeled-roots");
+
+    let mut network = NetworkState::builder()
+        .store_dir(&dir)
+        .expect("Opening the store dir should succeed")
+        .build();
+
+    let code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+
+    let contract =
+        Contract::new(&Counter::new(99), code.to_vec(), network.store());
+    let contract_id = network.deploy(contract).expect("Deploy error");
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    network.persist_as("finalized").expect("Persist error");
+    let finalized_root = network.root();
+
+    let (_, network) = network
+        .transact(contract_id, 0, Increment, &mut gas)
+        .expect("Transaction should succeed");
+    network.persist_as("accepted").expect("Persist error");
+    let accepted_root = network.root();
+
+    let (_, network) = network
+        .transact(contract_id, 0, Increment, &mut gas)
+        .expect("Transaction should succeed");
+    network.persist().expect("Persist error");
+    let tip_root = network.root();
+
+    let labels: Vec<_> = network
+        .persisted()
+        .expect("Reading the log should succeed")
+        .iter()
+        .map(|entry| (entry.label().map(String::from), entry.root()))
+        .collect();
+    assert_eq!(
+        labels,
+        vec![
+            (Some(String::from("finalized")), finalized_root),
+            (Some(String::from("accepted")), accepted_root),
+            (None, tip_root),
+        ]
+    );
+
+    // By default the latest persisted state is restored
+    let tip = NetworkState::builder()
+        .store_dir(&dir)
+        .expect("Opening the store dir should succeed")
+        .build();
+    assert_eq!(tip.root(), tip_root);
+    assert_eq!(
+        *tip.query(contract_id, 0, ReadValue, &mut gas).unwrap(),
+        101
+    );
+
+    let finalized = NetworkState::builder()
+        .store_dir(&dir)
+        .and_then(|builder| builder.at_label("finalized"))
+        .expect("Restoring a labeled state should succeed")
+        .build();
+    assert_eq!(finalized.root(), finalized_root);
+    assert_eq!(
+        *finalized
+            .query(contract_id, 0, ReadValue, &mut gas)
+            .unwrap(),
+        99
+    );
+
+    let accepted = NetworkState::builder()
+        .store_dir(&dir)
+        .and_then(|builder| builder.at_root(accepted_root))
+        .expect("Restoring a state by root should succeed")
+        .build();
+    assert_eq!(accepted.root(), accepted_root);
+    assert_eq!(
+        *accepted.query(contract_id, 0, ReadValue, &mut gas).unwrap(),
+        100
+    );
+
+    assert!(NetworkState::builder()
+        .store_dir(&dir)
+        .and_then(|builder| builder.at_label("unknown"))
+        .is_err());
+    assert!(NetworkState::builder().at_label("finalized").is_err());
+
+    fs::remove_dir_all(&dir).expect("Removing the store dir should succeed");
+}
+
+#[test]
+fn persist_hash_version() {
+    let dir = store_dir("hash-version");
+
+    let mut network = NetworkState::builder()
+        .store_dir(&dir)
+        .expect("Opening the store dir should succeed")
+        .build();
+
+    let code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+
+    let contract =
+        Contract::new(&Counter::new(99), code.to_vec(), network.store());
+    let contract_id = network.deploy(contract).expect("Deploy error");
+
+    network.persist().expect("Persist error");
+    let root = network.root();
+
+    let entries = network.persisted().expect("Reading the log should succeed");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].version(), HASH_VERSION);
+
+    let restored = NetworkState::builder()
+        .store_dir(&dir)
+        .expect("Opening the store dir should succeed")
+        .build();
+    assert_eq!(restored.root(), root);
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+    assert_eq!(
+        *restored.query(contract_id, 0, ReadValue, &mut gas).unwrap(),
+        99
+    );
+
+    fs::remove_dir_all(&dir).expect("Removing the store dir should succeed");
+}
+
+/// The size of the files in `dir` and its subdirectories.
+fn dir_size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .expect("Reading the dir should succeed")
+        .map(|entry| {
+            let entry = entry.expect("Reading the dir should succeed");
+            let metadata =
+                entry.metadata().expect("Reading metadata should succeed");
+            match metadata.is_dir() {
+                true => dir_size(&entry.path()),
+                false => metadata.len(),
+            }
+        })
+        .sum()
+}
+
+#[test]
+fn persist_compact() {
+    use stack::{Peek, Push, Stack};
+
+    const PUSHES: u64 = 1000;
+
+    let dir = store_dir("compact");
+    let compacted_dir = store_dir("compacted");
+
+    let code =
+        include_bytes!("../target/wasm32-unknown-unknown/release/stack.wasm");
+
+    let (contract_id, root) = {
+        let mut network = NetworkState::builder()
+            .store_dir(&dir)
+            .expect("Opening the store dir should succeed")
+            .build();
+
+        let contract =
+            Contract::new(&Stack::new(), code.to_vec(), network.store());
+        let contract_id = network.deploy(contract).expect("Deploy error");
+
+        network.persist_as("empty").expect("Persist error");
+
+        // Every push leaves the nodes it replaced behind in the store
+        let mut gas = GasMeter::with_limit(100_000_000_000);
+        for i in 0..PUSHES {
+            let (_, new_network) = network
+                .transact(contract_id, 0, Push::new(i), &mut gas)
+                .expect("Transaction should succeed");
+            network = new_network;
+        }
+        network.persist().expect("Persist error");
+
+        (contract_id, network.root())
+    };
+
+    NetworkState::compact(&dir, &compacted_dir)
+        .expect("Compacting the store should succeed");
+    assert!(dir_size(&compacted_dir) < dir_size(&dir));
+
+    // A directory already holding a store is not compacted into
+    assert!(NetworkState::compact(&dir, &compacted_dir).is_err());
+
+    let mut network = NetworkState::builder()
+        .store_dir(&compacted_dir)
+        .expect("Opening the compacted store should succeed")
+        .build();
+    assert_eq!(network.root(), root);
+    assert_eq!(
+        network
+            .persisted()
+            .expect("Reading the log should succeed")
+            .len(),
+        2
+    );
+
+    let mut gas = GasMeter::with_limit(100_000_000_000);
+    for i in 0..PUSHES {
+        let peeked = network
+            .query(contract_id, 0, Peek::new(i), &mut gas)
+            .expect("Query should succeed");
+        assert_eq!(*peeked, Some(i));
+    }
+
+    // The compacted store keeps being written to
+    let (_, new_network) = network
+        .transact(contract_id, 0, Push::new(PUSHES), &mut gas)
+        .expect("Transaction should succeed");
+    network = new_network;
+    network.persist().expect("Persist error");
+    let pushed_root = network.root();
+
+    let restored = NetworkState::builder()
+        .store_dir(&compacted_dir)
+        .expect("Opening the compacted store should succeed")
+        .build();
+    assert_eq!(restored.root(), pushed_root);
+    let peeked = restored
+        .query(contract_id, 0, Peek::new(PUSHES), &mut gas)
+        .expect("Query should succeed");
+    assert_eq!(*peeked, Some(PUSHES));
+
+    // Older states are restored from the compacted store as well
+    let empty = NetworkState::builder()
+        .store_dir(&compacted_dir)
+        .expect("Opening the compacted store should succeed")
+        .at_label("empty")
+        .expect("Restoring a compacted state should succeed")
+        .build();
+    let peeked = empty
+        .query(contract_id, 0, Peek::new(0), &mut gas)
+        .expect("Query should succeed");
+    assert_eq!(*peeked, None);
+
+    fs::remove_dir_all(&dir).expect("Removing the store dir should succeed");
+    fs::remove_dir_all(&compacted_dir)
+        .expect("Removing the store dir should succeed");
+}
+
+/// The layout of contracts persisted with `HASH_VERSION` 0.
+mod v0 {
+    use bytecheck::CheckBytes;
+    use dusk_hamt::{Hamt, KvPair};
+    use microkelvin::{
+        Annotation, Child, ChildMut, Combine, Compound, Link, OffsetLen,
+    };
+    use rkyv::{Archive, Deserialize, Serialize};
+    use rusk_vm::ContractId;
+
+    #[derive(Archive, Clone, Serialize, Deserialize)]
+    #[archive_attr(derive(CheckBytes))]
+    pub struct ContractData(Vec<u8>);
+
+    impl<A, I> Compound<A, I> for ContractData {
+        type Leaf = Vec<u8>;
+
+        fn child(&self, ofs: usize) -> Child<Self, A, I> {
+            match ofs {
+                0 => Child::Leaf(&self.0),
+                _ => Child::End,
+            }
+        }
+
+        fn child_mut(&mut self, ofs: usize) -> ChildMut<Self, A, I> {
+            match ofs {
+                0 => ChildMut::Leaf(&mut self.0),
+                _ => ChildMut::End,
+            }
+        }
+    }
+
+    #[derive(Archive, Clone, Serialize, Deserialize)]
+    #[archive_attr(derive(CheckBytes))]
+    pub struct Contract {
+        state: Link<ContractData, (), OffsetLen>,
+        code: Link<ContractData, (), OffsetLen>,
+    }
+
+    impl Contract {
+        pub fn new(state: Vec<u8>, code: Vec<u8>) -> Self {
+            Contract {
+                state: Link::new(ContractData(state)),
+                code: Link::new(ContractData(code)),
+            }
+        }
+    }
+
+    /// The annotations are rehashed when migrating, so only their layout
+    /// matters.
+    #[derive(
+        Default,
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Archive,
+        Serialize,
+        Deserialize,
+        CheckBytes,
+    )]
+    #[archive(as = "Self")]
+    pub struct HashAnnotation([u8; 32]);
+
+    impl Combine<HashAnnotation> for HashAnnotation {
+        fn combine(&mut self, _: &HashAnnotation) {}
+    }
+
+    impl Annotation<KvPair<ContractId, Contract>> for HashAnnotation {
+        fn from_leaf(_: &KvPair<ContractId, Contract>) -> Self {
+            Self::default()
+        }
+    }
+
+    pub type ContractsTree =
+        Hamt<ContractId, Contract, HashAnnotation, OffsetLen>;
+}
+
+#[test]
+fn persist_migrate_v0() {
+    use microkelvin::HostStore;
+    use rkyv::ser::serializers::AllocSerializer;
+    use rkyv::ser::Serializer;
+    use rusk_uplink::StoreContext;
+    use rusk_vm::ContractId;
+
+    let dir = store_dir("migrate-v0");
+
+    let code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+    let contract_id = ContractId::from([1; 32]);
+
+    // The state as it would be with the current version
+    let mut network = NetworkState::new();
+    let contract =
+        Contract::new(&Counter::new(99), code.to_vec(), network.store());
+    let state = contract.state().to_vec();
+    network
+        .deploy_with_id(contract_id, contract)
+        .expect("Deploy error");
+    let root = network.root();
+
+    // A store predating versioning, without a log
+    {
+        let store = StoreContext::new(
+            HostStore::with_file(&dir)
+                .expect("Opening the store should succeed"),
+        );
+
+        let mut contracts = v0::ContractsTree::new();
+        contracts.insert(contract_id, v0::Contract::new(state, code.to_vec()));
+
+        let stored = store.store(&contracts);
+        store
+            .persist()
+            .expect("Persisting the store should succeed");
+
+        let mut ser = AllocSerializer::<0>::default();
+        ser.serialize_value(stored.ident().erase()).unwrap();
+        fs::write(dir.join("persist_id"), ser.into_serializer().into_inner())
+            .expect("Writing the id should succeed");
+    }
+
+    let migrated = NetworkState::builder()
+        .store_dir(&dir)
+        .expect("Opening a version 0 store should succeed")
+        .build();
+    assert_eq!(migrated.root(), root);
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+    assert_eq!(
+        *migrated.query(contract_id, 0, ReadValue, &mut gas).unwrap(),
+        99
+    );
+
+    // The migrated state is recorded, and restored as is from then on
+    let entries = migrated
+        .persisted()
+        .expect("Reading the log should succeed");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].version(), HASH_VERSION);
+    assert_eq!(entries[0].root(), root);
+
+    let restored = NetworkState::builder()
+        .store_dir(&dir)
+        .expect("Opening the migrated store should succeed")
+        .build();
+    assert_eq!(restored.root(), root);
+    assert_eq!(
+        restored
+            .persisted()
+            .expect("Reading the log should succeed")
+            .len(),
+        1
+    );
+
+    fs::remove_dir_all(&dir).expect("Removing the store dir should succeed");
+}
+
+#[test]
+fn persist_corrupted_files() {
+    use microkelvin::OffsetLen;
+    use rkyv::ser::serializers::AllocSerializer;
+    use rkyv::ser::Serializer;
+
+    let dir = store_dir("corrupted-files");
+
+    let mut network = NetworkState::builder()
+        .store_dir(&dir)
+        .expect("Opening the store dir should succeed")
+        .build();
+
+    let code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+
+    let contract =
+        Contract::new(&Counter::new(99), code.to_vec(), network.store());
+    network.deploy(contract).expect("Deploy error");
+    network.persist().expect("Persist error");
+
+    // An entry whose label is out of bounds of the record
+    let log_path = dir.join("persist_log");
+    let mut log = fs::read(&log_path).expect("Reading the log should succeed");
+    let mut record = [0xff; 64];
+    record[..4].copy_from_slice(&60u32.to_le_bytes());
+    log.extend_from_slice(&record);
+    fs::write(&log_path, &log).expect("Writing the log should succeed");
+
+    let error = NetworkState::builder()
+        .store_dir(&dir)
+        .err()
+        .expect("Opening a store with a corrupted log should fail");
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    assert!(network.persisted().is_err());
+
+    // A persistence id too short to hold one
+    log.truncate(log.len() - record.len());
+    fs::write(&log_path, &log).expect("Writing the log should succeed");
+    fs::write(dir.join("persist_id"), [0xff; 3])
+        .expect("Writing the id should succeed");
+
+    let error = NetworkState::builder()
+        .store_dir(&dir)
+        .err()
+        .expect("Opening a store with a corrupted id should fail");
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+    // A persistence id past the end of the store
+    let mut ser = AllocSerializer::<0>::default();
+    ser.serialize_value(&OffsetLen::new(u32::MAX as u64, 64))
+        .unwrap();
+    fs::write(dir.join("persist_id"), ser.into_serializer().into_inner())
+        .expect("Writing the id should succeed");
+
+    let error = NetworkState::builder()
+        .store_dir(&dir)
+        .err()
+        .expect("Opening a store with an id past its end should fail");
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+    fs::remove_dir_all(&dir).expect("Removing the store dir should succeed");
+}
@@ -0,0 +1,153 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use dusk_bls12_381::BlsScalar;
+use dusk_bytes::Serializable;
+use precompiles::{
+    BlsVerify, Keccak256, PoseidonHash, Precompiles, SchnorrVerify, Sha256,
+};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rusk_vm::{Contract, ContractId, GasMeter, NetworkState};
+use sha2::Digest;
+
+fn deploy() -> (NetworkState, ContractId) {
+    let mut network = NetworkState::new();
+
+    let code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/precompiles.wasm"
+    );
+
+    let contract = Contract::new(&Precompiles, code.to_vec(), network.store());
+    let contract_id = network.deploy(contract).expect("Deploy error");
+
+    (network, contract_id)
+}
+
+#[test]
+fn hashes() {
+    let (network, contract_id) = deploy();
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    let inputs: [&[u8]; 3] = [&[], b"rusk", &[0x5a; 10_000]];
+    for input in inputs {
+        let sha256 = network
+            .query(contract_id, 0, Sha256::new(input), &mut gas)
+            .expect("Query should succeed");
+        assert_eq!(&sha256[..], &sha2::Sha256::digest(input)[..]);
+
+        let keccak256 = network
+            .query(contract_id, 0, Keccak256::new(input), &mut gas)
+            .expect("Query should succeed");
+        assert_eq!(&keccak256[..], &sha3::Keccak256::digest(input)[..]);
+    }
+}
+
+#[test]
+fn poseidon_hash() {
+    let (network, contract_id) = deploy();
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    let scalars = [BlsScalar::from(1), BlsScalar::from(2), BlsScalar::from(3)];
+    let bytes: Vec<_> = scalars.iter().map(|s| s.to_bytes()).collect();
+
+    let hash = network
+        .query(contract_id, 0, PoseidonHash::new(bytes), &mut gas)
+        .expect("Query should succeed");
+    assert_eq!(
+        *hash,
+        Some(dusk_poseidon::sponge::hash(&scalars).to_bytes())
+    );
+
+    // Scalars larger than the modulus are not canonical
+    let hash = network
+        .query(
+            contract_id,
+            0,
+            PoseidonHash::new(vec![[0xff; 32]]),
+            &mut gas,
+        )
+        .expect("Query should succeed");
+    assert_eq!(*hash, None);
+}
+
+#[test]
+fn schnorr_verify() {
+    use dusk_pki::{PublicKey, SecretKey};
+    use dusk_schnorr::Signature;
+
+    let (network, contract_id) = deploy();
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+    let sk = SecretKey::random(&mut rng);
+    let pk = PublicKey::from(&sk);
+
+    let msg = BlsScalar::from(42);
+    let sig = Signature::new(&sk, &mut rng, msg);
+
+    let mut verify = |pk: &[u8], msg: BlsScalar, sig: &[u8]| {
+        *network
+            .query(
+                contract_id,
+                0,
+                SchnorrVerify::new(pk, msg.to_bytes(), sig),
+                &mut gas,
+            )
+            .expect("Query should succeed")
+    };
+
+    assert!(verify(&pk.to_bytes(), msg, &sig.to_bytes()));
+
+    // Wrong message
+    assert!(!verify(
+        &pk.to_bytes(),
+        BlsScalar::from(43),
+        &sig.to_bytes()
+    ));
+
+    // Wrong key
+    let other = PublicKey::from(&SecretKey::random(&mut rng));
+    assert!(!verify(&other.to_bytes(), msg, &sig.to_bytes()));
+
+    // Malformed signature
+    assert!(!verify(&pk.to_bytes(), msg, &[0xff; Signature::SIZE]));
+    assert!(!verify(&pk.to_bytes(), msg, &[]));
+}
+
+#[test]
+fn bls_verify() {
+    use dusk_bls12_381_sign::{PublicKey, SecretKey, Signature};
+
+    let (network, contract_id) = deploy();
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+    let sk = SecretKey::random(&mut rng);
+    let pk = PublicKey::from(&sk);
+
+    let msg = b"hello rusk";
+    let sig = sk.sign(&pk, msg);
+
+    let mut verify = |pk: &[u8], sig: &[u8], msg: &[u8]| {
+        *network
+            .query(contract_id, 0, BlsVerify::new(pk, sig, msg), &mut gas)
+            .expect("Query should succeed")
+    };
+
+    assert!(verify(&pk.to_bytes(), &sig.to_bytes(), msg));
+
+    // Wrong message
+    assert!(!verify(&pk.to_bytes(), &sig.to_bytes(), b"hello dusk"));
+
+    // Wrong key
+    let other = PublicKey::from(&SecretKey::random(&mut rng));
+    assert!(!verify(&other.to_bytes(), &sig.to_bytes(), msg));
+
+    // Malformed signature
+    assert!(!verify(&pk.to_bytes(), &[0xff; Signature::SIZE], msg));
+    assert!(!verify(&pk.to_bytes(), &[], msg));
+}
@@ -0,0 +1,302 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use dusk_plonk::circuit::VerifierData;
+use dusk_plonk::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rusk_uplink::PROOF_TRANSCRIPT_LABEL;
+use rusk_vm::{Config, Contract, GasMeter, HostCosts, NetworkState, VMError};
+use verifier::{Verifier, VerifyProof};
+
+/// Proves knowledge of two scalars adding up to a public one.
+#[derive(Debug, Default)]
+struct SumCircuit {
+    a: BlsScalar,
+    b: BlsScalar,
+    sum: BlsScalar,
+}
+
+impl Circuit for SumCircuit {
+    const CIRCUIT_ID: [u8; 32] = [0xff; 32];
+
+    fn gadget(&mut self, composer: &mut TurboComposer) -> Result<(), Error> {
+        let a = composer.append_witness(self.a);
+        let b = composer.append_witness(self.b);
+
+        // a + b - sum = 0
+        let constraint = Constraint::new()
+            .left(1)
+            .right(1)
+            .public(-self.sum)
+            .a(a)
+            .b(b);
+        composer.append_gate(constraint);
+
+        Ok(())
+    }
+
+    fn public_inputs(&self) -> Vec<PublicInputValue> {
+        vec![self.sum.into()]
+    }
+
+    fn padded_gates(&self) -> usize {
+        1 << 4
+    }
+}
+
+/// A proof of the circuit along with what is needed to verify it.
+struct Fixture {
+    pp: PublicParameters,
+    verifier_data: Vec<u8>,
+    proof: Vec<u8>,
+    sum: [u8; 32],
+}
+
+fn fixture() -> Fixture {
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let pp = PublicParameters::setup(1 << 6, &mut rng)
+        .expect("Setting up the parameters should succeed");
+
+    let (prover_key, verifier_data) = SumCircuit::default()
+        .compile(&pp)
+        .expect("Compiling the circuit should succeed");
+
+    let (a, b) = (BlsScalar::from(20), BlsScalar::from(22));
+    let sum = a + b;
+
+    let proof = SumCircuit { a, b, sum }
+        .prove(&pp, &prover_key, PROOF_TRANSCRIPT_LABEL)
+        .expect("Proving should succeed");
+
+    Fixture {
+        pp,
+        verifier_data: verifier_data.to_var_bytes(),
+        proof: proof.to_bytes().to_vec(),
+        sum: sum.to_bytes(),
+    }
+}
+
+fn deploy(network: &mut NetworkState) -> rusk_vm::ContractId {
+    let code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/verifier.wasm"
+    );
+
+    let contract = Contract::new(&Verifier, code.to_vec(), network.store());
+    network.deploy(contract).expect("Deploy error")
+}
+
+#[test]
+fn verify_proof() {
+    let Fixture {
+        pp,
+        verifier_data,
+        proof,
+        sum,
+    } = fixture();
+
+    let mut network = NetworkState::builder().public_parameters(pp).build();
+    let contract_id = deploy(&mut network);
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    let mut verify = |verifier_data: &[u8], proof: &[u8], sum: [u8; 32]| {
+        *network
+            .query(
+                contract_id,
+                0,
+                VerifyProof::new(verifier_data, proof, vec![sum]),
+                &mut gas,
+            )
+            .expect("Query should succeed")
+    };
+
+    assert!(verify(&verifier_data, &proof, sum));
+    // The second call uses the cached verifier data
+    assert!(verify(&verifier_data, &proof, sum));
+
+    // Wrong public input
+    let wrong_sum = BlsScalar::from(43).to_bytes();
+    assert!(!verify(&verifier_data, &proof, wrong_sum));
+
+    // Tampered proof
+    let mut tampered = proof.clone();
+    tampered[0] ^= 0xff;
+    assert!(!verify(&verifier_data, &tampered, sum));
+
+    // Malformed verifier data and proof
+    assert!(!verify(&verifier_data[..16], &proof, sum));
+    assert!(!verify(&verifier_data, &proof[..16], sum));
+}
+
+#[test]
+fn verify_proof_public_inputs() {
+    let Fixture {
+        pp,
+        verifier_data,
+        proof,
+        sum,
+    } = fixture();
+
+    let mut network = NetworkState::builder().public_parameters(pp).build();
+    let contract_id = deploy(&mut network);
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    let mut verify = |verifier_data: &[u8], inputs: Vec<[u8; 32]>| {
+        *network
+            .query(
+                contract_id,
+                0,
+                VerifyProof::new(verifier_data, &proof, inputs),
+                &mut gas,
+            )
+            .expect("Query should succeed")
+    };
+
+    assert!(verify(&verifier_data, vec![sum]));
+
+    // Too few and too many public inputs
+    assert!(!verify(&verifier_data, vec![]));
+    assert!(!verify(&verifier_data, vec![sum, sum]));
+
+    // Verifier data placing the public input past the end of the circuit
+    let vd = VerifierData::from_slice(&verifier_data)
+        .expect("Parsing the verifier data should succeed");
+    let padded_gates = vd.key().padded_gates();
+    let out_of_range =
+        VerifierData::new(vd.key().clone(), vec![padded_gates]).to_var_bytes();
+    assert!(!verify(&out_of_range, vec![sum]));
+}
+
+#[test]
+fn verify_proof_cached() {
+    let Fixture {
+        pp,
+        verifier_data,
+        proof,
+        sum,
+    } = fixture();
+
+    let mut network = NetworkState::builder().public_parameters(pp).build();
+    let contract_id = deploy(&mut network);
+
+    let mut verify = |verifier_data: &[u8], proof: &[u8]| {
+        let mut gas = GasMeter::with_limit(1_000_000_000);
+        let valid = *network
+            .query(
+                contract_id,
+                0,
+                VerifyProof::new(verifier_data, proof, vec![sum]),
+                &mut gas,
+            )
+            .expect("Query should succeed");
+        (valid, gas.spent())
+    };
+
+    // The second call finds the verifier data in the cache, and returns the
+    // same result at the same cost
+    let first = verify(&verifier_data, &proof);
+    let second = verify(&verifier_data, &proof);
+    assert!(first.0);
+    assert_eq!(first, second);
+
+    // Cached verifier data is still checked against the proof
+    let mut tampered = proof.clone();
+    tampered[0] ^= 0xff;
+    assert!(!verify(&verifier_data, &tampered).0);
+
+    // Malformed verifier data is rejected on every call
+    assert!(!verify(&verifier_data[..16], &proof).0);
+    assert!(!verify(&verifier_data[..16], &proof).0);
+}
+
+const DEFAULT_CONFIG: Config = Config::new();
+
+const EXPENSIVE_PROOFS: Config = Config {
+    host_costs: HostCosts {
+        verify_proof_per_byte: 11,
+        verify_proof_per_input: 13,
+        ..HostCosts::new()
+    },
+    ..Config::new()
+};
+
+/// Verifies the fixture's proof, returning the gas spent and the number of
+/// bytes of verifier data and proof passed to the host.
+fn verify_fixture_with_config(config: &'static Config) -> (u64, u64) {
+    let Fixture {
+        pp,
+        verifier_data,
+        proof,
+        sum,
+    } = fixture();
+
+    let mut network = NetworkState::builder()
+        .config(config)
+        .public_parameters(pp)
+        .build();
+    let contract_id = deploy(&mut network);
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+    let valid = network
+        .query(
+            contract_id,
+            0,
+            VerifyProof::new(&verifier_data, &proof, vec![sum]),
+            &mut gas,
+        )
+        .expect("Query should succeed");
+    assert!(*valid);
+
+    let bytes = (verifier_data.len() + proof.len()) as u64;
+    (gas.spent(), bytes)
+}
+
+#[test]
+fn verify_proof_gas_per_byte_and_input() {
+    let (cheap, bytes) = verify_fixture_with_config(&DEFAULT_CONFIG);
+    let (expensive, _) = verify_fixture_with_config(&EXPENSIVE_PROOFS);
+
+    let costs = &EXPENSIVE_PROOFS.host_costs;
+    let default_costs = &DEFAULT_CONFIG.host_costs;
+
+    // The fixture's proof has a single public input
+    assert_eq!(
+        expensive - cheap,
+        bytes
+            * (costs.verify_proof_per_byte
+                - default_costs.verify_proof_per_byte)
+            + (costs.verify_proof_per_input
+                - default_costs.verify_proof_per_input)
+    );
+}
+
+#[test]
+fn verify_proof_without_public_parameters() {
+    let Fixture {
+        verifier_data,
+        proof,
+        sum,
+        ..
+    } = fixture();
+
+    let mut network = NetworkState::new();
+    let contract_id = deploy(&mut network);
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+
+    assert!(matches!(
+        network.query(
+            contract_id,
+            0,
+            VerifyProof::new(verifier_data, proof, vec![sum]),
+            &mut gas,
+        ),
+        Err(VMError::MissingPublicParameters)
+    ));
+}
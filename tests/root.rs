@@ -5,7 +5,9 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use counter::{Counter, Increment};
-use rusk_vm::{Contract, GasMeter, NetworkState};
+use rusk_vm::{
+    Contract, ContractChange, ContractId, GasMeter, NetworkState, VMError,
+};
 
 #[test]
 fn root_properties() {
@@ -48,3 +50,419 @@ fn root_properties() {
         "root should be deterministic"
     );
 }
+
+#[test]
+fn state_proofs() {
+    let mut network = NetworkState::new();
+
+    let code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+
+    let mut states = Vec::new();
+    for i in 0..u8::MAX {
+        let counter = Counter::new(i as i32);
+        let contract_id = ContractId::reserved(i);
+        let contract = Contract::new(&counter, code.to_vec(), network.store());
+        states.push(contract.state().to_vec());
+        network
+            .deploy_with_id(contract_id, contract)
+            .expect("contract should be inserted successfully");
+    }
+
+    let root = network.root();
+
+    for (i, state) in states.iter().enumerate() {
+        let contract_id = ContractId::reserved(i as u8);
+        let proof = network
+            .prove_contract(&contract_id)
+            .expect("proving a deployed contract should succeed");
+
+        assert!(
+            proof.verify(&root, &contract_id, state),
+            "proof should verify against the root"
+        );
+
+        let other_id = ContractId::reserved((i as u8).wrapping_add(1));
+        assert!(!proof.verify(&root, &other_id, state));
+        assert!(!proof.verify(&root, &contract_id, &states[0][1..]));
+        assert!(!proof.verify(&[0; 32], &contract_id, state));
+    }
+
+    let unknown_id = ContractId::reserved(u8::MAX);
+    assert!(matches!(
+        network.prove_contract(&unknown_id),
+        Err(VMError::UnknownContract(id)) if id == unknown_id
+    ));
+
+    let contract_id = ContractId::reserved(42);
+    let old_proof = network.prove_contract(&contract_id).unwrap();
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+    let (_, network) = network
+        .transact(contract_id, 0, Increment, &mut gas)
+        .expect("transaction should succeed");
+
+    let new_root = network.root();
+    let new_state =
+        Contract::new(&Counter::new(43), code.to_vec(), network.store())
+            .state()
+            .to_vec();
+
+    assert!(!old_proof.verify(&new_root, &contract_id, &new_state));
+
+    let new_proof = network.prove_contract(&contract_id).unwrap();
+    assert!(new_proof.verify(&new_root, &contract_id, &new_state));
+    assert!(!new_proof.verify(&new_root, &contract_id, &states[42]));
+}
+
+#[test]
+fn state_proof_tampering() {
+    use rusk_uplink::{
+        leaf_hash, tree_slots, ProofLevel, StateProof, TREE_ARITY,
+    };
+
+    let mut network = NetworkState::new();
+
+    let code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+
+    let mut states = Vec::new();
+    for i in 0..u8::MAX {
+        let counter = Counter::new(i as i32);
+        let contract_id = ContractId::reserved(i);
+        let contract = Contract::new(&counter, code.to_vec(), network.store());
+        states.push(contract.state().to_vec());
+        network
+            .deploy_with_id(contract_id, contract)
+            .expect("contract should be inserted successfully");
+    }
+
+    let root = network.root();
+
+    let contract_id = ContractId::reserved(42);
+    let state = &states[42];
+
+    let proof = network.prove_contract(&contract_id).unwrap();
+    assert!(proof.verify(&root, &contract_id, state));
+    assert!(
+        proof.levels.len() > 1,
+        "the contract should not be at the root"
+    );
+
+    let slots = tree_slots(&contract_id);
+    let depth = proof.levels.len();
+    let leaf_slot = slots[depth - 1];
+
+    let leaf_level = &proof.levels[0];
+    let sibling_slot = (0..TREE_ARITY)
+        .find(|slot| leaf_level.siblings[*slot].is_some())
+        .expect("the leaf should have siblings");
+
+    // A tampered level
+    let mut tampered = proof.clone();
+    tampered.levels[0].siblings[sibling_slot].as_mut().unwrap()[0] ^= 0xff;
+    assert!(!tampered.verify(&root, &contract_id, state));
+
+    // A sibling swapped with the path
+    let mut swapped = proof.clone();
+    swapped.levels[0].siblings.swap(leaf_slot, sibling_slot);
+    assert!(!swapped.verify(&root, &contract_id, state));
+
+    // Missing or extra levels
+    let mut truncated = proof.clone();
+    truncated.levels.pop();
+    assert!(!truncated.verify(&root, &contract_id, state));
+
+    let mut extended = proof.clone();
+    extended.levels.push(ProofLevel::default());
+    assert!(!extended.verify(&root, &contract_id, state));
+
+    assert!(!StateProof::default().verify(&root, &contract_id, state));
+
+    // The node holding the leaf presented as a leaf itself, with the hashes
+    // of its children laid out as the fields of the leaf
+    let mut children = proof.levels[0].siblings;
+    children[leaf_slot] = Some(leaf_hash(
+        &contract_id,
+        &proof.code_hash,
+        &proof.storage_root,
+        proof.nonce,
+        state,
+    ));
+    let fields: Vec<u8> =
+        children.iter().flatten().flatten().copied().collect();
+
+    let mut code_hash = [0u8; 32];
+    code_hash.copy_from_slice(&fields[..32]);
+    let mut storage_root = [0u8; 32];
+    storage_root.copy_from_slice(&fields[32..64]);
+    let mut nonce = [0u8; 8];
+    let nonce_len = (fields.len() - 64).min(8);
+    nonce[..nonce_len].copy_from_slice(&fields[64..64 + nonce_len]);
+
+    let forged = StateProof {
+        code_hash,
+        storage_root,
+        nonce: u64::from_le_bytes(nonce),
+        levels: proof.levels[1..].to_vec(),
+    };
+    let forged_state = &fields[64 + nonce_len..];
+    assert!(!forged.verify(&root, &contract_id, forged_state));
+}
+
+#[test]
+fn root_commits_to_code() {
+    let counter = Counter::new(99);
+    let contract_id = ContractId::reserved(0);
+
+    let code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+    let other_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/minimal_counter.wasm"
+    );
+
+    let mut network = NetworkState::new();
+    let mut other_network = NetworkState::new();
+
+    let contract = Contract::new(&counter, code.to_vec(), network.store());
+    let other_contract =
+        Contract::new(&counter, other_code.to_vec(), other_network.store());
+    assert_eq!(contract.state(), other_contract.state());
+
+    network
+        .deploy_with_id(contract_id, contract.clone())
+        .expect("contract should be inserted successfully");
+    other_network
+        .deploy_with_id(contract_id, other_contract.clone())
+        .expect("contract should be inserted successfully");
+
+    assert_ne!(
+        network.root(),
+        other_network.root(),
+        "root should differ with different code under the same id and state"
+    );
+
+    let root = network.root();
+    let other_root = other_network.root();
+
+    network
+        .replace_contract(contract_id, other_contract)
+        .expect("contract should be replaced successfully");
+    assert_eq!(
+        network.root(),
+        other_root,
+        "root should change with the code of a contract"
+    );
+
+    network
+        .replace_contract(contract_id, contract)
+        .expect("contract should be replaced successfully");
+    assert_eq!(network.root(), root, "root should be deterministic");
+
+    let proof = network.prove_contract(&contract_id).unwrap();
+    let other_proof = other_network.prove_contract(&contract_id).unwrap();
+    assert_ne!(proof.code_hash, other_proof.code_hash);
+
+    let state = Contract::new(&counter, code.to_vec(), network.store())
+        .state()
+        .to_vec();
+    assert!(proof.verify(&root, &contract_id, &state));
+    assert!(!other_proof.verify(&root, &contract_id, &state));
+}
+
+#[test]
+fn state_diff() {
+    let mut network = NetworkState::new();
+
+    let code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+    let other_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/minimal_counter.wasm"
+    );
+
+    for i in 0..u8::MAX {
+        let counter = Counter::new(i as i32);
+        let contract_id = ContractId::reserved(i);
+        let contract = Contract::new(&counter, code.to_vec(), network.store());
+        network
+            .deploy_with_id(contract_id, contract)
+            .expect("contract should be inserted successfully");
+    }
+
+    assert!(network.diff(&network.clone()).is_empty());
+
+    let incremented = ContractId::reserved(3);
+    let removed = ContractId::reserved(5);
+    let upgraded = ContractId::reserved(7);
+    let added = ContractId::reserved(u8::MAX);
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+    let (_, mut fork) = network
+        .transact(incremented, 0, Increment, &mut gas)
+        .expect("transaction should succeed");
+
+    fork.remove_contract(&removed)
+        .expect("contract should be removed successfully");
+
+    let upgrade =
+        Contract::new(&Counter::new(7), other_code.to_vec(), fork.store());
+    fork.replace_contract(upgraded, upgrade)
+        .expect("contract should be replaced successfully");
+
+    let contract = Contract::new(&Counter::new(0), code.to_vec(), fork.store());
+    fork.deploy_with_id(added, contract)
+        .expect("contract should be inserted successfully");
+
+    let sorted = |mut changes: Vec<ContractChange>| {
+        changes.sort_by(|a, b| {
+            a.contract_id().as_bytes().cmp(b.contract_id().as_bytes())
+        });
+        changes
+    };
+
+    assert_eq!(
+        sorted(network.diff(&fork)),
+        vec![
+            ContractChange::StateChanged(incremented),
+            ContractChange::Removed(removed),
+            ContractChange::CodeChanged(upgraded),
+            ContractChange::Added(added),
+        ]
+    );
+    assert_eq!(
+        sorted(fork.diff(&network)),
+        vec![
+            ContractChange::StateChanged(incremented),
+            ContractChange::Added(removed),
+            ContractChange::CodeChanged(upgraded),
+            ContractChange::Removed(added),
+        ]
+    );
+}
+
+#[test]
+fn state_diff_nonce() {
+    use factory::{Deploy, Factory};
+
+    let mut network = NetworkState::new();
+
+    let factory_code =
+        include_bytes!("../target/wasm32-unknown-unknown/release/factory.wasm");
+    let counter_code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+
+    let factory =
+        Contract::new(&Factory, factory_code.to_vec(), network.store());
+    let factory_id = network.deploy(factory).unwrap();
+
+    let init_state =
+        Contract::new(&Counter::new(7), counter_code.to_vec(), network.store())
+            .state()
+            .to_vec();
+
+    let mut gas = GasMeter::with_limit(1_000_000_000);
+    let (receipt, mut fork) = network
+        .transact(
+            factory_id,
+            0,
+            Deploy::new(&counter_code[..], &init_state),
+            &mut gas,
+        )
+        .expect("Deploying from a contract should succeed");
+
+    // Removing the deployed contract leaves the factory with a new nonce
+    fork.remove_contract(&*receipt)
+        .expect("contract should be removed successfully");
+    assert_ne!(network.root(), fork.root());
+
+    assert_eq!(
+        network.diff(&fork),
+        vec![ContractChange::NonceChanged(factory_id)]
+    );
+    assert_eq!(
+        fork.diff(&network),
+        vec![ContractChange::NonceChanged(factory_id)]
+    );
+}
+
+#[test]
+fn list_contracts() {
+    let mut network = NetworkState::new();
+
+    assert_eq!(network.contracts().count(), 0);
+
+    let code = include_bytes!(
+        "../target/wasm32-unknown-unknown/release/deps/counter.wasm"
+    );
+
+    for i in 0..u8::MAX {
+        let counter = Counter::new(i as i32);
+        let contract_id = ContractId::reserved(i);
+        let contract = Contract::new(&counter, code.to_vec(), network.store());
+        network
+            .deploy_with_id(contract_id, contract)
+            .expect("contract should be inserted successfully");
+    }
+
+    let contracts: Vec<_> = network.contracts().collect();
+    assert_eq!(contracts.len(), u8::MAX as usize);
+
+    let code_hash = rusk_uplink::code_hash(code);
+    for (_, info) in &contracts {
+        assert_eq!(info.code_hash(), &code_hash);
+        assert_eq!(info.code_size(), code.len());
+        assert!(info.state_size() > 0);
+    }
+
+    let mut ids: Vec<_> = contracts.iter().map(|(id, _)| *id).collect();
+    ids.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+    ids.dedup();
+    assert_eq!(ids.len(), u8::MAX as usize);
+
+    // Paging through the contracts yields them all, in the same order
+    const PAGE_SIZE: usize = 16;
+
+    let mut paged = vec![];
+    let mut page: Vec<_> = network.contracts().take(PAGE_SIZE).collect();
+    while let Some((last, _)) = page.last().cloned() {
+        paged.append(&mut page);
+        page = network.contracts_after(&last).take(PAGE_SIZE).collect();
+    }
+    assert_eq!(paged, contracts);
+
+    // The order of the tree is the order of the paths of the contracts
+    let paths: Vec<_> = contracts
+        .iter()
+        .map(|(id, _)| rusk_uplink::tree_slots(id))
+        .collect();
+    assert!(paths.windows(2).all(|pair| pair[0] < pair[1]));
+
+    // Starting after a contract that is not in the state resumes at the one
+    // that would follow it
+    let unknown = ContractId::reserved(u8::MAX);
+    let unknown_path = rusk_uplink::tree_slots(&unknown);
+    let following: Vec<_> = contracts
+        .iter()
+        .filter(|(id, _)| rusk_uplink::tree_slots(id) > unknown_path)
+        .cloned()
+        .collect();
+    assert_eq!(
+        network.contracts_after(&unknown).collect::<Vec<_>>(),
+        following
+    );
+
+    // Paging carries on when the last contract of a page is removed before
+    // the next one is requested
+    let (removed, _) = contracts[PAGE_SIZE - 1];
+    network
+        .remove_contract(&removed)
+        .expect("contract should be removed successfully");
+
+    let rest: Vec<_> = network.contracts_after(&removed).collect();
+    assert_eq!(rest, contracts[PAGE_SIZE..]);
+}